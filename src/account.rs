@@ -0,0 +1,280 @@
+use crate::domain::*;
+use crate::errors::Error;
+use crate::market::Market;
+use crate::operations::{Operations, OperationsExt};
+use crate::orders::Orders;
+use crate::portfolio::Portfolio;
+use crate::sandbox::Sandbox;
+use crate::user::User;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset, TimeZone};
+
+/// Client handle bound to a single broker account.
+///
+/// Account scoped calls always send `brokerAccountId`, so they can't fall back
+/// to the default account by accident.
+pub struct AccountClient<'a, C> {
+    client: &'a C,
+    broker_account_id: BrokerAccountId,
+}
+
+impl<'a, C> AccountClient<'a, C> {
+    pub fn new(client: &'a C, broker_account_id: &BrokerAccountId) -> Self {
+        Self {
+            client: client,
+            broker_account_id: broker_account_id.clone(),
+        }
+    }
+
+    pub fn broker_account_id(&self) -> &BrokerAccountId {
+        &self.broker_account_id
+    }
+}
+
+impl<C: Orders + Sync> AccountClient<'_, C> {
+    pub async fn orders(&self) -> Result<Response<Vec<OrdersPayload>>, Error> {
+        self.client.orders(Some(&self.broker_account_id)).await
+    }
+
+    pub async fn make_limit_order(
+        &self,
+        figi: &Figi,
+        operation: Operation,
+        lots: i32,
+        price: Amount,
+    ) -> Result<Response<LimitOrderPayload>, Error> {
+        self.client
+            .make_limit_order(figi, Some(&self.broker_account_id), operation, lots, price)
+            .await
+    }
+
+    pub async fn make_market_order(
+        &self,
+        figi: &Figi,
+        operation: Operation,
+        lots: i32,
+    ) -> Result<Response<MarketOrderPayload>, Error> {
+        self.client
+            .make_market_order(figi, Some(&self.broker_account_id), operation, lots)
+            .await
+    }
+
+    pub async fn cancel_order(&self, order_id: &OrderId) -> Result<Response<EmptyPayload>, Error> {
+        self.client
+            .cancel_order(order_id, Some(&self.broker_account_id))
+            .await
+    }
+}
+
+impl<C: Portfolio + Sync> AccountClient<'_, C> {
+    pub async fn portfolio(&self) -> Result<Response<PortfolioPayload>, Error> {
+        self.client.portfolio(Some(&self.broker_account_id)).await
+    }
+
+    pub async fn portfolio_currencies(
+        &self,
+    ) -> Result<Response<PortfolioCurrenciesPayload>, Error> {
+        self.client
+            .portfolio_currencies(Some(&self.broker_account_id))
+            .await
+    }
+}
+
+impl<C: Operations + Sync> AccountClient<'_, C> {
+    pub async fn operations<Tz>(
+        &self,
+        from: &DateTime<Tz>,
+        to: &DateTime<Tz>,
+        figi: Option<&Figi>,
+    ) -> Result<Response<OperationsPayload>, Error>
+    where
+        Tz: TimeZone,
+        Tz::Offset: Sync,
+    {
+        self.client
+            .operations_tz(from, to, figi, Some(&self.broker_account_id))
+            .await
+    }
+}
+
+impl<C: Sandbox + Sync> AccountClient<'_, C> {
+    pub async fn set_currencies_balance(
+        &self,
+        currency: Currency,
+        balance: Amount,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.client
+            .set_currencies_balance(Some(&self.broker_account_id), currency, balance)
+            .await
+    }
+
+    pub async fn set_positions_balance(
+        &self,
+        figi: &Figi,
+        balance: Amount,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.client
+            .set_positions_balance(Some(&self.broker_account_id), figi, balance)
+            .await
+    }
+
+    pub async fn remove(&self) -> Result<Response<EmptyPayload>, Error> {
+        self.client.remove(Some(&self.broker_account_id)).await
+    }
+
+    pub async fn clear(&self) -> Result<Response<EmptyPayload>, Error> {
+        self.client.clear(Some(&self.broker_account_id)).await
+    }
+}
+
+#[async_trait]
+impl<C: Market + Sync> Market for AccountClient<'_, C> {
+    async fn stocks(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.client.stocks().await
+    }
+
+    async fn bonds(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.client.bonds().await
+    }
+
+    async fn etfs(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.client.etfs().await
+    }
+
+    async fn currencies(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.client.currencies().await
+    }
+
+    async fn order_book(
+        &self,
+        figi: &Figi,
+        depth: i32,
+    ) -> Result<Response<OrderBookPayload>, Error> {
+        self.client.order_book(figi, depth).await
+    }
+
+    async fn candles(
+        &self,
+        figi: &Figi,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        interval: &Interval,
+    ) -> Result<Response<CandlesPayload>, Error> {
+        self.client.candles(figi, from, to, interval).await
+    }
+
+    async fn search_by_figi(
+        &self,
+        figi: &Figi,
+    ) -> Result<Response<SearchMarketInstrumentPayload>, Error> {
+        self.client.search_by_figi(figi).await
+    }
+
+    async fn search_by_ticker(
+        &self,
+        ticker: &Ticker,
+    ) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.client.search_by_ticker(ticker).await
+    }
+}
+
+#[async_trait]
+impl<C: User + Sync> User for AccountClient<'_, C> {
+    async fn accounts(&self) -> Result<Response<AccountsPayload>, Error> {
+        self.client.accounts().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::user::User;
+    use crate::TinkoffInvestClient;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn make_market_order() {
+        let mock = mockito::mock("POST", "/orders/market-order")
+            .match_header("Authorization", "Bearer token123")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("brokerAccountId".to_string(), "account_iis".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
+            ]))
+            .match_body(Matcher::JsonString(
+                "{\"operation\":\"Sell\",\"lots\":3}".to_string(),
+            ))
+            .with_body(
+                "{
+                        \"trackingId\": \"tracking_id_0\",
+                        \"status\": \"Ok\",
+                        \"payload\": {
+                            \"orderId\": \"order_0\",
+                            \"operation\": \"Sell\",
+                            \"status\": \"Fill\",
+                            \"requestedLots\": 3,
+                            \"executedLots\": 3
+                        }
+                }",
+            )
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, "token123");
+        let account = tinkoff.account(&BrokerAccountId::new("account_iis").unwrap());
+
+        assert_eq!(account.broker_account_id(), "account_iis");
+        account
+            .make_market_order(&Figi::new("BBG000B9XRY4").unwrap(), Operation::Sell, 3)
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn account_by_type() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_query(Matcher::Missing)
+            .with_body(
+                "{
+                        \"trackingId\": \"tracking_id_0\",
+                        \"status\": \"Ok\",
+                        \"payload\": {
+                            \"accounts\": [
+                                {
+                                    \"brokerAccountType\": \"Tinkoff\",
+                                    \"brokerAccountId\": \"account_0\"
+                                },
+                                {
+                                    \"brokerAccountType\": \"TinkoffIis\",
+                                    \"brokerAccountId\": \"account_iis\"
+                                }
+                            ]
+                        }
+                }",
+            )
+            .expect(2)
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, "token123");
+
+        let iis = tinkoff
+            .account_by_type(BrokerAccountType::TinkoffIis)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(iis.broker_account_id, "account_iis");
+
+        let account = tinkoff.account(&iis.broker_account_id);
+        let tinkoff_account = account
+            .account_by_type(BrokerAccountType::Tinkoff)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(tinkoff_account.broker_account_id, "account_0");
+
+        mock.assert();
+    }
+}
@@ -0,0 +1,196 @@
+use crate::domain::*;
+use crate::errors::Error;
+use crate::market::Market;
+use chrono::{DateTime, FixedOffset};
+use futures::stream::{self, BoxStream, StreamExt};
+
+/// Market data requests for many instruments at once.
+///
+/// At most `concurrency` requests are in flight, and results are yielded as they
+/// complete, each paired with its figi. A failure only affects its own item, so
+/// collect into a `HashMap` or handle errors per instrument. Every entry of `figis`
+/// is requested, duplicates included, and a `HashMap` keeps only one result per figi:
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use futures::StreamExt;
+/// use tinkoff_invest_client::{domain::Figi, MarketBatch, TinkoffInvestClient};
+///
+/// async fn books(client: &TinkoffInvestClient, figis: &[Figi]) {
+///     let books: HashMap<_, _> = client.order_books_batch(figis, 10, 4).collect().await;
+/// }
+/// ```
+///
+/// Requests still go through the client's rate limiter, so a large batch is paced
+/// rather than rejected with HTTP 429.
+pub trait MarketBatch: Market + Sync {
+    fn candles_batch<'a>(
+        &'a self,
+        figis: &'a [Figi],
+        from: &'a DateTime<FixedOffset>,
+        to: &'a DateTime<FixedOffset>,
+        interval: &'a Interval,
+        concurrency: usize,
+    ) -> BoxStream<'a, (Figi, Result<Response<CandlesPayload>, Error>)> {
+        stream::iter(figis)
+            .map(move |figi| async move {
+                (figi.clone(), self.candles(figi, from, to, interval).await)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
+
+    fn order_books_batch<'a>(
+        &'a self,
+        figis: &'a [Figi],
+        depth: i32,
+        concurrency: usize,
+    ) -> BoxStream<'a, (Figi, Result<Response<OrderBookPayload>, Error>)> {
+        stream::iter(figis)
+            .map(move |figi| async move { (figi.clone(), self.order_book(figi, depth).await) })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
+
+    fn search_by_figi_batch<'a>(
+        &'a self,
+        figis: &'a [Figi],
+        concurrency: usize,
+    ) -> BoxStream<'a, (Figi, Result<Response<SearchMarketInstrumentPayload>, Error>)> {
+        stream::iter(figis)
+            .map(move |figi| async move { (figi.clone(), self.search_by_figi(figi).await) })
+            .buffer_unordered(concurrency.max(1))
+            .boxed()
+    }
+}
+
+impl<T: Market + Sync> MarketBatch for T {}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::batch::MarketBatch;
+    use crate::domain::*;
+    use crate::transport::{HttpRequest, HttpResponse, HttpTransport};
+    use crate::{Environment, Error, TinkoffInvestClient};
+    use async_trait::async_trait;
+    use futures::StreamExt;
+    use mockito::Matcher;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Answers every order book request after a delay, recording how many overlap.
+    #[derive(Default)]
+    struct CountingTransport {
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl HttpTransport for CountingTransport {
+        async fn send(&self, _request: HttpRequest) -> Result<HttpResponse, Error> {
+            let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+            self.calls.fetch_add(1, Ordering::SeqCst);
+
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Ok(HttpResponse {
+                status: 200,
+                headers: vec![],
+                body: "{
+                    \"trackingId\": \"tracking_id_0\",
+                    \"status\": \"Ok\",
+                    \"payload\": {
+                        \"figi\": \"BBG000B9XRY4\",
+                        \"depth\": 1,
+                        \"bids\": [],
+                        \"asks\": [],
+                        \"tradeStatus\": \"NormalTrading\",
+                        \"minPriceIncrement\": 0.01
+                    }
+                }"
+                .to_string(),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn concurrency_limit() {
+        let transport = Arc::new(CountingTransport::default());
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: "http://fake".to_string(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .transport(transport.clone())
+            .build()
+            .unwrap();
+
+        let figis = vec![Figi::new("BBG000B9XRY4").unwrap(); 10];
+        let results: Vec<_> = tinkoff.order_books_batch(&figis, 1, 3).collect().await;
+
+        assert_eq!(results.len(), 10);
+        assert!(results.iter().all(|(_, result)| result.is_ok()));
+        assert_eq!(transport.calls.load(Ordering::SeqCst), 10);
+        assert_eq!(transport.max_in_flight.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn search_by_figi_batch() {
+        let mocks: Vec<_> = ["BBG000BPH459", "BBG000BVPV84"]
+            .iter()
+            .map(|figi| {
+                mockito::mock("GET", "/market/search/by-figi")
+                    .match_query(Matcher::UrlEncoded("figi".to_string(), figi.to_string()))
+                    .with_body(format!(
+                        "{{
+                            \"trackingId\": \"tracking_id_0\",
+                            \"status\": \"Ok\",
+                            \"payload\": {{
+                                \"figi\": \"{}\",
+                                \"ticker\": \"ticker_0\",
+                                \"lot\": 1,
+                                \"currency\": \"RUB\",
+                                \"name\": \"name_0\",
+                                \"type\": \"Stock\"
+                            }}
+                        }}",
+                        figi
+                    ))
+                    .create()
+            })
+            .collect();
+        let failing = mockito::mock("GET", "/market/search/by-figi")
+            .match_query(Matcher::UrlEncoded(
+                "figi".to_string(),
+                "BBG000N9MNX3".to_string(),
+            ))
+            .with_status(500)
+            .with_body("{\"trackingId\": \"tracking_id_0\",\"status\": \"Error\",\"payload\": {\"message\": \"Internal error\",\"code\": \"INTERNAL_ERROR\"}}")
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, "token123");
+
+        let figis: Vec<Figi> = ["BBG000BPH459", "BBG000BVPV84", "BBG000N9MNX3"]
+            .iter()
+            .map(|figi| Figi::new(figi).unwrap())
+            .collect();
+        let results: HashMap<_, _> = tinkoff.search_by_figi_batch(&figis, 2).collect().await;
+
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[&figis[1]].as_ref().unwrap().payload.figi, figis[1]);
+        assert!(results[&figis[0]].is_ok());
+        assert!(results[&figis[2]].is_err());
+
+        for mock in mocks {
+            mock.assert();
+        }
+        failing.assert();
+    }
+}
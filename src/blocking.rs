@@ -0,0 +1,516 @@
+//! Synchronous API for code that doesn't run a tokio runtime.
+//!
+//! [`Client`] wraps any of the async clients and drives it on its own
+//! single-threaded runtime. The traits here mirror the async ones and are
+//! implemented for a [`Client`] whenever the wrapped client implements the
+//! async trait, so a `Client<SandboxClient>` is the only one with [`Sandbox`].
+//!
+//! Don't call it from inside an async runtime: blocking there panics.
+//!
+//! ```no_run
+//! use tinkoff_invest_client::blocking::{Client, Portfolio};
+//! use tinkoff_invest_client::TinkoffInvestClient;
+//!
+//! let client = TinkoffInvestClient::builder("token").build_production().unwrap();
+//! let client = Client::new(client).unwrap();
+//! let portfolio = client.portfolio(None).unwrap();
+//! ```
+
+use crate::delegate::Inner;
+use crate::domain::*;
+use crate::errors::Error;
+use chrono::{DateTime, FixedOffset};
+use futures::sink::{Sink, SinkExt};
+use futures::stream::{Stream, StreamExt};
+use std::future::Future;
+use std::pin::Pin;
+use tokio::runtime::{Builder, Runtime};
+
+/// Blocking wrapper around an async client.
+pub struct Client<C> {
+    client: C,
+    runtime: Runtime,
+}
+
+impl<C> Client<C> {
+    pub fn new(client: C) -> Result<Self, Error> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::GeneralError {
+                description: format!("Can't start runtime: {}", e),
+            })?;
+
+        Ok(Self {
+            client: client,
+            runtime: runtime,
+        })
+    }
+
+    pub fn get_ref(&self) -> &C {
+        &self.client
+    }
+
+    pub fn into_inner(self) -> C {
+        self.client
+    }
+
+    fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+}
+
+impl<C: Inner> Client<C> {
+    /// Opens the streaming connection. Incoming events are read by iterating over the result.
+    pub fn get_stream(&self) -> Result<EventStream<'_>, Error> {
+        let (sink, stream) = self.block_on(self.client.inner().get_stream())?;
+
+        Ok(EventStream {
+            runtime: &self.runtime,
+            sink: Box::pin(sink),
+            stream: Box::pin(stream),
+        })
+    }
+}
+
+/// Streaming connection of a blocking [`Client`].
+///
+/// Iterating blocks until the next event arrives and ends once the connection is gone.
+/// The connection only answers server pings while it's being iterated.
+pub struct EventStream<'a> {
+    runtime: &'a Runtime,
+    sink: Pin<Box<dyn Sink<OutcomeEvent, Error = Error> + 'a>>,
+    stream: Pin<Box<dyn Stream<Item = Result<IncomeEvent, Error>> + 'a>>,
+}
+
+impl EventStream<'_> {
+    pub fn send(&mut self, event: OutcomeEvent) -> Result<(), Error> {
+        self.runtime.block_on(self.sink.send(event))
+    }
+
+    pub fn close(mut self) -> Result<(), Error> {
+        self.runtime.block_on(self.sink.close())
+    }
+}
+
+impl Iterator for EventStream<'_> {
+    type Item = Result<IncomeEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.stream.next())
+    }
+}
+
+pub trait Market {
+    fn stocks(&self) -> Result<Response<MarketInstrumentListPayload>, Error>;
+
+    fn bonds(&self) -> Result<Response<MarketInstrumentListPayload>, Error>;
+
+    fn etfs(&self) -> Result<Response<MarketInstrumentListPayload>, Error>;
+
+    fn currencies(&self) -> Result<Response<MarketInstrumentListPayload>, Error>;
+
+    fn order_book(&self, figi: &Figi, depth: i32) -> Result<Response<OrderBookPayload>, Error>;
+
+    fn candles(
+        &self,
+        figi: &Figi,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        interval: &Interval,
+    ) -> Result<Response<CandlesPayload>, Error>;
+
+    fn search_by_figi(&self, figi: &Figi)
+        -> Result<Response<SearchMarketInstrumentPayload>, Error>;
+
+    fn search_by_ticker(
+        &self,
+        ticker: &Ticker,
+    ) -> Result<Response<MarketInstrumentListPayload>, Error>;
+}
+
+pub trait Orders {
+    fn orders(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<Vec<OrdersPayload>>, Error>;
+
+    fn make_limit_order(
+        &self,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
+        operation: Operation,
+        lots: i32,
+        price: Amount,
+    ) -> Result<Response<LimitOrderPayload>, Error>;
+
+    fn make_market_order(
+        &self,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
+        operation: Operation,
+        lots: i32,
+    ) -> Result<Response<MarketOrderPayload>, Error>;
+
+    fn cancel_order(
+        &self,
+        order_id: &OrderId,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error>;
+}
+
+pub trait Portfolio {
+    fn portfolio(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<PortfolioPayload>, Error>;
+
+    fn portfolio_currencies(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<PortfolioCurrenciesPayload>, Error>;
+}
+
+pub trait Operations {
+    fn operations(
+        &self,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        figi: Option<&Figi>,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<OperationsPayload>, Error>;
+}
+
+pub trait User {
+    fn accounts(&self) -> Result<Response<AccountsPayload>, Error>;
+
+    /// First account of the given type, if the user has one.
+    fn account_by_type(
+        &self,
+        broker_account_type: BrokerAccountType,
+    ) -> Result<Option<UserAccount>, Error>;
+}
+
+pub trait Sandbox {
+    fn register(
+        &self,
+        broker_account_type: BrokerAccountType,
+    ) -> Result<Response<SandboxAccount>, Error>;
+
+    fn set_currencies_balance(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+        currency: Currency,
+        balance: Amount,
+    ) -> Result<Response<EmptyPayload>, Error>;
+
+    fn set_positions_balance(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+        figi: &Figi,
+        balance: Amount,
+    ) -> Result<Response<EmptyPayload>, Error>;
+
+    fn remove(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error>;
+
+    fn clear(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error>;
+}
+
+impl<C: crate::Market> Market for Client<C> {
+    fn stocks(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.block_on(self.client.stocks())
+    }
+
+    fn bonds(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.block_on(self.client.bonds())
+    }
+
+    fn etfs(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.block_on(self.client.etfs())
+    }
+
+    fn currencies(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.block_on(self.client.currencies())
+    }
+
+    fn order_book(&self, figi: &Figi, depth: i32) -> Result<Response<OrderBookPayload>, Error> {
+        self.block_on(self.client.order_book(figi, depth))
+    }
+
+    fn candles(
+        &self,
+        figi: &Figi,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        interval: &Interval,
+    ) -> Result<Response<CandlesPayload>, Error> {
+        self.block_on(self.client.candles(figi, from, to, interval))
+    }
+
+    fn search_by_figi(
+        &self,
+        figi: &Figi,
+    ) -> Result<Response<SearchMarketInstrumentPayload>, Error> {
+        self.block_on(self.client.search_by_figi(figi))
+    }
+
+    fn search_by_ticker(
+        &self,
+        ticker: &Ticker,
+    ) -> Result<Response<MarketInstrumentListPayload>, Error> {
+        self.block_on(self.client.search_by_ticker(ticker))
+    }
+}
+
+impl<C: crate::Orders> Orders for Client<C> {
+    fn orders(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<Vec<OrdersPayload>>, Error> {
+        self.block_on(self.client.orders(broker_account_id))
+    }
+
+    fn make_limit_order(
+        &self,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
+        operation: Operation,
+        lots: i32,
+        price: Amount,
+    ) -> Result<Response<LimitOrderPayload>, Error> {
+        self.block_on(
+            self.client
+                .make_limit_order(figi, broker_account_id, operation, lots, price),
+        )
+    }
+
+    fn make_market_order(
+        &self,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
+        operation: Operation,
+        lots: i32,
+    ) -> Result<Response<MarketOrderPayload>, Error> {
+        self.block_on(
+            self.client
+                .make_market_order(figi, broker_account_id, operation, lots),
+        )
+    }
+
+    fn cancel_order(
+        &self,
+        order_id: &OrderId,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.block_on(self.client.cancel_order(order_id, broker_account_id))
+    }
+}
+
+impl<C: crate::Portfolio> Portfolio for Client<C> {
+    fn portfolio(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<PortfolioPayload>, Error> {
+        self.block_on(self.client.portfolio(broker_account_id))
+    }
+
+    fn portfolio_currencies(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<PortfolioCurrenciesPayload>, Error> {
+        self.block_on(self.client.portfolio_currencies(broker_account_id))
+    }
+}
+
+impl<C: crate::Operations> Operations for Client<C> {
+    fn operations(
+        &self,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        figi: Option<&Figi>,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<OperationsPayload>, Error> {
+        self.block_on(self.client.operations(from, to, figi, broker_account_id))
+    }
+}
+
+impl<C: crate::User + Sync> User for Client<C> {
+    fn accounts(&self) -> Result<Response<AccountsPayload>, Error> {
+        self.block_on(self.client.accounts())
+    }
+
+    fn account_by_type(
+        &self,
+        broker_account_type: BrokerAccountType,
+    ) -> Result<Option<UserAccount>, Error> {
+        self.block_on(self.client.account_by_type(broker_account_type))
+    }
+}
+
+impl<C: crate::Sandbox> Sandbox for Client<C> {
+    fn register(
+        &self,
+        broker_account_type: BrokerAccountType,
+    ) -> Result<Response<SandboxAccount>, Error> {
+        self.block_on(self.client.register(broker_account_type))
+    }
+
+    fn set_currencies_balance(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+        currency: Currency,
+        balance: Amount,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.block_on(
+            self.client
+                .set_currencies_balance(broker_account_id, currency, balance),
+        )
+    }
+
+    fn set_positions_balance(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+        figi: &Figi,
+        balance: Amount,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.block_on(
+            self.client
+                .set_positions_balance(broker_account_id, figi, balance),
+        )
+    }
+
+    fn remove(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.block_on(self.client.remove(broker_account_id))
+    }
+
+    fn clear(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error> {
+        self.block_on(self.client.clear(broker_account_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::blocking::{Client, Portfolio, Sandbox};
+    use crate::domain::*;
+    use crate::{Environment, TinkoffInvestClient};
+    use futures::{SinkExt, StreamExt};
+    use mockito::Matcher;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    #[test]
+    fn portfolio() {
+        let mock = mockito::mock("GET", "/portfolio")
+            .match_header("Authorization", "Bearer token123")
+            .match_query(Matcher::UrlEncoded(
+                "brokerAccountId".to_string(),
+                "account_123".to_string(),
+            ))
+            .with_body(
+                "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"positions\": []}}",
+            )
+            .create();
+
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .build_production()
+            .unwrap();
+        let client = Client::new(tinkoff).unwrap();
+
+        client
+            .portfolio(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[test]
+    fn sandbox() {
+        let mock = mockito::mock("POST", "/sandbox/clear")
+            .match_query(Matcher::UrlEncoded(
+                "brokerAccountId".to_string(),
+                "account_123".to_string(),
+            ))
+            .with_body("{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {}}")
+            .create();
+
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .build_sandbox()
+            .unwrap();
+        let client = Client::new(tinkoff).unwrap();
+
+        client
+            .clear(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .unwrap();
+
+        mock.assert();
+    }
+
+    #[test]
+    fn stream() {
+        let server = tokio::runtime::Runtime::new().unwrap();
+        let listener = server.block_on(TcpListener::bind("127.0.0.1:0")).unwrap();
+        let address = listener.local_addr().unwrap();
+
+        server.spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+
+            let subscription = ws.next().await.unwrap().unwrap();
+            assert!(subscription.to_text().unwrap().contains("candle:subscribe"));
+
+            ws.send(Message::text(
+                "{\"event\": \"error\", \"time\": \"2021-11-01T10:00:00+03:00\", \
+                 \"payload\": {\"error\": \"Unknown figi\", \"request_id\": \"r1\"}}",
+            ))
+            .await
+            .unwrap();
+            ws.close(None).await.unwrap();
+        });
+
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: "http://127.0.0.1:1".to_string(),
+                streaming: format!("ws://{}", address),
+            })
+            .build()
+            .unwrap();
+        let client = Client::new(tinkoff).unwrap();
+
+        let mut stream = client.get_stream().unwrap();
+        stream
+            .send(OutcomeEvent::CandleSubscribe {
+                figi: Figi::new("BBG000B9XRY4").unwrap(),
+                interval: Interval::_1min,
+                request_id: Some("r1".to_string()),
+            })
+            .unwrap();
+
+        match stream.next() {
+            Some(Ok(IncomeEvent::Error { payload, .. })) => {
+                assert_eq!(payload.error, "Unknown figi")
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,276 @@
+use crate::errors::Error;
+use crate::middleware::Interceptor;
+use crate::rate_limit::RateLimiter;
+use crate::retry::RetryPolicy;
+use crate::token::SecretToken;
+use crate::transport::{HttpTransport, ReqwestTransport};
+use crate::{ProductionClient, SandboxClient, TinkoffInvestClient};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{Client, Proxy};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+pub const PRODUCTION_ENDPOINT: &str = "https://api-invest.tinkoff.ru/openapi";
+pub const SANDBOX_ENDPOINT: &str = "https://api-invest.tinkoff.ru/openapi/sandbox";
+pub const STREAMING_ENDPOINT: &str = "wss://api-invest.tinkoff.ru/openapi/md/v1/md-openapi/ws";
+
+const APP_NAME_HEADER: &str = "x-app-name";
+
+/// Set of endpoints the client talks to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Environment {
+    Production,
+    Sandbox,
+    /// Endpoints of a self-hosted proxy or a test deployment.
+    Custom {
+        rest: String,
+        streaming: String,
+    },
+}
+
+impl Environment {
+    pub fn rest_endpoint(&self) -> &str {
+        match self {
+            Environment::Production => PRODUCTION_ENDPOINT,
+            Environment::Sandbox => SANDBOX_ENDPOINT,
+            Environment::Custom { rest, .. } => rest,
+        }
+    }
+
+    pub fn streaming_endpoint(&self) -> &str {
+        match self {
+            Environment::Production | Environment::Sandbox => STREAMING_ENDPOINT,
+            Environment::Custom { streaming, .. } => streaming,
+        }
+    }
+}
+
+pub struct TinkoffInvestClientBuilder {
+    token: SecretToken,
+    environment: Environment,
+    streaming_endpoint: Option<String>,
+    connect_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    app_name: Option<String>,
+    proxy: Option<Proxy>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    transport: Option<Arc<dyn HttpTransport>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
+}
+
+impl TinkoffInvestClientBuilder {
+    pub fn new<T: Into<SecretToken>>(token: T) -> Self {
+        Self {
+            token: token.into(),
+            environment: Environment::Production,
+            streaming_endpoint: None,
+            connect_timeout: None,
+            timeout: None,
+            user_agent: None,
+            app_name: None,
+            proxy: None,
+            retry_policy: None,
+            rate_limiter: None,
+            transport: None,
+            interceptors: vec![],
+        }
+    }
+
+    pub fn environment(mut self, environment: Environment) -> Self {
+        self.environment = environment;
+        self
+    }
+
+    /// Overrides the streaming endpoint of the environment.
+    pub fn streaming_endpoint(mut self, streaming_endpoint: &str) -> Self {
+        self.streaming_endpoint = Some(streaming_endpoint.to_string());
+        self
+    }
+
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Total time allowed for a single REST request, from connecting until the body is read.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Application name reported to the broker in the `x-app-name` header.
+    pub fn app_name(mut self, app_name: &str) -> Self {
+        self.app_name = Some(app_name.to_string());
+        self
+    }
+
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Retry failed requests according to `retry_policy`. Requests are not retried by default.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Throttle requests on the client side. Share the limiter between clients using the same token.
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Send REST requests through `transport` instead of a reqwest client built from
+    /// the timeout, user agent, app name and proxy settings, which are then ignored.
+    pub fn transport(mut self, transport: Arc<dyn HttpTransport>) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Register an interceptor; can be called several times.
+    pub fn interceptor(mut self, interceptor: Arc<dyn Interceptor>) -> Self {
+        self.interceptors.push(interceptor);
+        self
+    }
+
+    pub fn build(self) -> Result<TinkoffInvestClient, Error> {
+        let transport = match &self.transport {
+            Some(transport) => transport.clone(),
+            None => Arc::new(ReqwestTransport::new(self.http_client()?)),
+        };
+
+        let streaming_endpoint = self
+            .streaming_endpoint
+            .unwrap_or_else(|| self.environment.streaming_endpoint().to_string());
+
+        Ok(TinkoffInvestClient {
+            transport: transport,
+            endpoint: self.environment.rest_endpoint().to_string(),
+            ws_endpoint: streaming_endpoint,
+            token: Arc::new(RwLock::new(self.token)),
+            retry_policy: self.retry_policy,
+            rate_limiter: self.rate_limiter,
+            interceptors: self.interceptors,
+        })
+    }
+
+    /// Builds a [`SandboxClient`], refusing the production endpoint and production tokens.
+    pub fn build_sandbox(self) -> Result<SandboxClient, Error> {
+        self.build()?.into_sandbox()
+    }
+
+    /// Builds a [`ProductionClient`], refusing the sandbox endpoint and sandbox tokens.
+    pub fn build_production(self) -> Result<ProductionClient, Error> {
+        self.build()?.into_production()
+    }
+
+    fn http_client(&self) -> Result<Client, Error> {
+        let mut http_client = Client::builder();
+
+        if let Some(connect_timeout) = self.connect_timeout {
+            http_client = http_client.connect_timeout(connect_timeout);
+        }
+
+        if let Some(timeout) = self.timeout {
+            http_client = http_client.timeout(timeout);
+        }
+
+        if let Some(user_agent) = &self.user_agent {
+            http_client = http_client.user_agent(user_agent);
+        }
+
+        if let Some(app_name) = &self.app_name {
+            let value = HeaderValue::from_str(app_name).map_err(|e| Error::ConfigurationError {
+                description: format!("Invalid app name {:?}: {}", app_name, e),
+            })?;
+            let mut headers = HeaderMap::new();
+            headers.insert(APP_NAME_HEADER, value);
+            http_client = http_client.default_headers(headers);
+        }
+
+        if let Some(proxy) = &self.proxy {
+            http_client = http_client.proxy(proxy.clone());
+        }
+
+        Ok(http_client.build()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::builder::{Environment, PRODUCTION_ENDPOINT, SANDBOX_ENDPOINT, STREAMING_ENDPOINT};
+    use crate::user::User;
+    use crate::TinkoffInvestClient;
+    use mockito::Matcher;
+    use std::time::Duration;
+
+    #[test]
+    fn environment_endpoints() {
+        assert_eq!(Environment::Production.rest_endpoint(), PRODUCTION_ENDPOINT);
+        assert_eq!(Environment::Sandbox.rest_endpoint(), SANDBOX_ENDPOINT);
+        assert_eq!(
+            Environment::Production.streaming_endpoint(),
+            STREAMING_ENDPOINT
+        );
+
+        let custom = Environment::Custom {
+            rest: "http://localhost:1234".to_string(),
+            streaming: "ws://localhost:1235".to_string(),
+        };
+        assert_eq!(custom.rest_endpoint(), "http://localhost:1234");
+        assert_eq!(custom.streaming_endpoint(), "ws://localhost:1235");
+    }
+
+    #[tokio::test]
+    async fn custom_headers() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_header("Authorization", "Bearer token123")
+            .match_header("user-agent", "my-agent/1.0")
+            .match_header("x-app-name", "my-app")
+            .match_query(Matcher::Missing)
+            .with_body(
+                "{
+                        \"trackingId\": \"tracking_id_0\",
+                        \"status\": \"Ok\",
+                        \"payload\": {
+                            \"accounts\": []
+                        }
+                }",
+            )
+            .create();
+
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .connect_timeout(Duration::from_secs(1))
+            .timeout(Duration::from_secs(5))
+            .user_agent("my-agent/1.0")
+            .app_name("my-app")
+            .build()
+            .unwrap();
+
+        tinkoff.accounts().await.unwrap();
+
+        mock.assert();
+    }
+
+    #[test]
+    fn invalid_app_name() {
+        let result = TinkoffInvestClient::builder("token123")
+            .app_name("bad\nname")
+            .build();
+
+        assert!(result.is_err());
+    }
+}
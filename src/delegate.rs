@@ -0,0 +1,251 @@
+//! Trait implementations for wrapper clients that forward every call to their
+//! inner `client` field. Each wrapper picks the traits it exposes.
+
+/// Access to the client a wrapper forwards to. It can't be named outside the crate,
+/// so it doesn't leak [`TinkoffInvestClient`](crate::TinkoffInvestClient) out of the restricted wrappers.
+#[cfg(feature = "blocking")]
+pub trait Inner {
+    fn inner(&self) -> &crate::TinkoffInvestClient;
+}
+
+#[cfg(feature = "blocking")]
+impl Inner for crate::TinkoffInvestClient {
+    fn inner(&self) -> &crate::TinkoffInvestClient {
+        self
+    }
+}
+
+macro_rules! delegate_market {
+    ($client:ty) => {
+        #[async_trait::async_trait]
+        impl crate::market::Market for $client {
+            async fn stocks(
+                &self,
+            ) -> Result<crate::domain::Response<crate::domain::MarketInstrumentListPayload>, crate::errors::Error>
+            {
+                self.client.stocks().await
+            }
+
+            async fn bonds(
+                &self,
+            ) -> Result<crate::domain::Response<crate::domain::MarketInstrumentListPayload>, crate::errors::Error>
+            {
+                self.client.bonds().await
+            }
+
+            async fn etfs(
+                &self,
+            ) -> Result<crate::domain::Response<crate::domain::MarketInstrumentListPayload>, crate::errors::Error>
+            {
+                self.client.etfs().await
+            }
+
+            async fn currencies(
+                &self,
+            ) -> Result<crate::domain::Response<crate::domain::MarketInstrumentListPayload>, crate::errors::Error>
+            {
+                self.client.currencies().await
+            }
+
+            async fn order_book(
+                &self,
+                figi: &crate::domain::Figi,
+                depth: i32,
+            ) -> Result<crate::domain::Response<crate::domain::OrderBookPayload>, crate::errors::Error> {
+                self.client.order_book(figi, depth).await
+            }
+
+            async fn candles(
+                &self,
+                figi: &crate::domain::Figi,
+                from: &chrono::DateTime<chrono::FixedOffset>,
+                to: &chrono::DateTime<chrono::FixedOffset>,
+                interval: &crate::domain::Interval,
+            ) -> Result<crate::domain::Response<crate::domain::CandlesPayload>, crate::errors::Error> {
+                self.client.candles(figi, from, to, interval).await
+            }
+
+            async fn search_by_figi(
+                &self,
+                figi: &crate::domain::Figi,
+            ) -> Result<
+                crate::domain::Response<crate::domain::SearchMarketInstrumentPayload>,
+                crate::errors::Error,
+            > {
+                self.client.search_by_figi(figi).await
+            }
+
+            async fn search_by_ticker(
+                &self,
+                ticker: &crate::domain::Ticker,
+            ) -> Result<crate::domain::Response<crate::domain::MarketInstrumentListPayload>, crate::errors::Error>
+            {
+                self.client.search_by_ticker(ticker).await
+            }
+        }
+    };
+}
+
+macro_rules! delegate_orders {
+    ($client:ty) => {
+        #[async_trait::async_trait]
+        impl crate::orders::Orders for $client {
+            async fn orders(
+                &self,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+            ) -> Result<
+                crate::domain::Response<Vec<crate::domain::OrdersPayload>>,
+                crate::errors::Error,
+            > {
+                self.client.orders(broker_account_id).await
+            }
+
+            async fn make_limit_order(
+                &self,
+                figi: &crate::domain::Figi,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+                operation: crate::domain::Operation,
+                lots: i32,
+                price: crate::domain::Amount,
+            ) -> Result<
+                crate::domain::Response<crate::domain::LimitOrderPayload>,
+                crate::errors::Error,
+            > {
+                self.client
+                    .make_limit_order(figi, broker_account_id, operation, lots, price)
+                    .await
+            }
+
+            async fn make_market_order(
+                &self,
+                figi: &crate::domain::Figi,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+                operation: crate::domain::Operation,
+                lots: i32,
+            ) -> Result<
+                crate::domain::Response<crate::domain::MarketOrderPayload>,
+                crate::errors::Error,
+            > {
+                self.client
+                    .make_market_order(figi, broker_account_id, operation, lots)
+                    .await
+            }
+
+            async fn cancel_order(
+                &self,
+                order_id: &crate::domain::OrderId,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+            ) -> Result<crate::domain::Response<crate::domain::EmptyPayload>, crate::errors::Error>
+            {
+                self.client.cancel_order(order_id, broker_account_id).await
+            }
+        }
+    };
+}
+
+macro_rules! delegate_portfolio {
+    ($client:ty) => {
+        #[async_trait::async_trait]
+        impl crate::portfolio::Portfolio for $client {
+            async fn portfolio(
+                &self,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+            ) -> Result<
+                crate::domain::Response<crate::domain::PortfolioPayload>,
+                crate::errors::Error,
+            > {
+                self.client.portfolio(broker_account_id).await
+            }
+
+            async fn portfolio_currencies(
+                &self,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+            ) -> Result<
+                crate::domain::Response<crate::domain::PortfolioCurrenciesPayload>,
+                crate::errors::Error,
+            > {
+                self.client.portfolio_currencies(broker_account_id).await
+            }
+        }
+    };
+}
+
+macro_rules! delegate_operations {
+    ($client:ty) => {
+        #[async_trait::async_trait]
+        impl crate::operations::Operations for $client {
+            async fn operations(
+                &self,
+                from: &chrono::DateTime<chrono::FixedOffset>,
+                to: &chrono::DateTime<chrono::FixedOffset>,
+                figi: Option<&crate::domain::Figi>,
+                broker_account_id: Option<&crate::domain::BrokerAccountId>,
+            ) -> Result<
+                crate::domain::Response<crate::domain::OperationsPayload>,
+                crate::errors::Error,
+            > {
+                self.client
+                    .operations(from, to, figi, broker_account_id)
+                    .await
+            }
+        }
+    };
+}
+
+macro_rules! delegate_user {
+    ($client:ty) => {
+        #[async_trait::async_trait]
+        impl crate::user::User for $client {
+            async fn accounts(
+                &self,
+            ) -> Result<crate::domain::Response<crate::domain::AccountsPayload>, crate::errors::Error> {
+                self.client.accounts().await
+            }
+        }
+    };
+}
+
+/// Inherent helpers every wrapper around [`TinkoffInvestClient`](crate::TinkoffInvestClient) has.
+macro_rules! delegate_client {
+    ($client:ty) => {
+        impl $client {
+            /// Handle sending `broker_account_id` with every account scoped call.
+            pub fn account(
+                &self,
+                broker_account_id: &crate::domain::BrokerAccountId,
+            ) -> crate::account::AccountClient<'_, Self> {
+                crate::account::AccountClient::new(self, broker_account_id)
+            }
+
+            pub async fn get_stream(
+                &self,
+            ) -> Result<
+                (
+                    impl futures::sink::Sink<crate::domain::OutcomeEvent, Error = crate::errors::Error>,
+                    impl futures::stream::Stream<
+                        Item = Result<crate::domain::IncomeEvent, crate::errors::Error>,
+                    >,
+                ),
+                crate::errors::Error,
+            > {
+                self.client.get_stream().await
+            }
+
+            /// Streaming connection that reconnects on failures and restores subscriptions,
+            /// see [`StreamingSession`](crate::StreamingSession).
+            pub async fn streaming_session(
+                &self,
+                config: crate::session::SessionConfig,
+            ) -> Result<crate::session::StreamingSession, crate::errors::Error> {
+                self.client.streaming_session(config).await
+            }
+        }
+
+        #[cfg(feature = "blocking")]
+        impl crate::delegate::Inner for $client {
+            fn inner(&self) -> &crate::TinkoffInvestClient {
+                &self.client
+            }
+        }
+    };
+}
@@ -1,62 +1,274 @@
-use chrono::{DateTime, Local};
+use crate::errors::Error;
+use chrono::{DateTime, FixedOffset};
 use serde_derive::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+pub use crate::protocol::{
+    CandleEventPayload, ErrorEventPayload, IncomeEvent, InstrumentInfoEventPayload,
+    OrderBookEventPayload, OutcomeEvent,
+};
+
+/// Type of money amounts and prices. It's `f64` unless the `decimal` feature
+/// switches it to [`rust_decimal::Decimal`]. Decimals are read from the shortest
+/// representation of the JSON number, so prices of up to 15 significant digits
+/// come out exact.
+#[cfg(not(feature = "decimal"))]
+pub type Amount = f64;
+#[cfg(feature = "decimal")]
+pub type Amount = rust_decimal::Decimal;
+
+/// Enum sent as a string on the wire. Values added by the broker later are kept
+/// in `Unknown` instead of failing the whole response.
+macro_rules! wire_enum {
+    (pub enum $name:ident { $($variant:ident = $wire:literal,)* }) => {
+        #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($variant,)*
+            Unknown(String),
+        }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum Currency {
-    RUB,
-    USD,
-    EUR,
-    GBP,
-    HKD,
-    CHF,
-    JPY,
-    CNY,
-    TRY,
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $wire,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<&str> for $name {
+            fn from(value: &str) -> Self {
+                match value {
+                    $($wire => $name::$variant,)*
+                    _ => $name::Unknown(value.to_string()),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let value = String::deserialize(deserializer)?;
+                Ok($name::from(value.as_str()))
+            }
+        }
+    };
+}
+
+/// Identifier that is checked on construction: it can't be empty or contain whitespace,
+/// and an optional `check` function validates its format.
+/// Values from the broker are taken as they are, since e.g. bonds and currencies come
+/// without an ISIN.
+macro_rules! identifier {
+    ($(#[$meta:meta])* pub struct $name:ident; $(check = $check:ident;)?) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+        #[serde(into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn new(value: &str) -> Result<Self, Error> {
+                Self::try_from(value.to_string())
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = Error;
+
+            fn try_from(value: String) -> Result<Self, Error> {
+                let valid = !value.is_empty() && !value.chars().any(char::is_whitespace);
+                if !valid $(|| !$check(&value))? {
+                    return Err(Error::ValidationError {
+                        description: format!("Invalid {} {:?}", stringify!($name), value),
+                    });
+                }
+
+                Ok(Self(value))
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = Error;
+
+            fn try_from(value: &str) -> Result<Self, Error> {
+                Self::new(value)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(value: &str) -> Result<Self, Error> {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                String::deserialize(deserializer).map($name)
+            }
+        }
+    };
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum Operation {
-    Buy,
-    Sell,
+identifier! {
+    /// Financial Instrument Global Identifier, e.g. `BBG000B9XRY4`.
+    pub struct Figi;
+    check = is_figi;
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum OrderStatus {
-    New,
-    PartiallyFill,
-    Fill,
-    Cancelled,
-    Replaced,
-    PendingCancel,
-    Rejected,
-    PendingReplace,
-    PendingNew,
+identifier! {
+    /// Exchange ticker, e.g. `AAPL`.
+    pub struct Ticker;
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum OrderType {
-    Limit,
-    Market,
+identifier! {
+    /// International Securities Identification Number, e.g. `US0378331005`.
+    pub struct Isin;
+    check = is_isin;
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum InstrumentType {
-    Stock,
-    Currency,
-    Bond,
-    Etf,
+/// Twelve uppercase letters and digits issued by Bloomberg, hence the `BBG` prefix.
+fn is_figi(value: &str) -> bool {
+    value.len() == 12 && value.starts_with("BBG") && is_upper_alphanumeric(value)
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum TradeStatus {
-    NormalTrading,
-    NotAvailableForTrading,
+/// Twelve uppercase letters and digits starting with a two-letter country code.
+fn is_isin(value: &str) -> bool {
+    value.len() == 12
+        && value.chars().take(2).all(|c| c.is_ascii_uppercase())
+        && is_upper_alphanumeric(value)
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum BrokerAccountType {
-    Tinkoff,
-    TinkoffIis,
+fn is_upper_alphanumeric(value: &str) -> bool {
+    value
+        .chars()
+        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit())
+}
+
+identifier! {
+    pub struct BrokerAccountId;
+}
+
+identifier! {
+    pub struct OrderId;
+}
+
+identifier! {
+    /// Request id assigned by the broker, useful when contacting support.
+    pub struct TrackingId;
+}
+
+wire_enum! {
+    pub enum Currency {
+        RUB = "RUB",
+        USD = "USD",
+        EUR = "EUR",
+        GBP = "GBP",
+        HKD = "HKD",
+        CHF = "CHF",
+        JPY = "JPY",
+        CNY = "CNY",
+        TRY = "TRY",
+    }
+}
+
+wire_enum! {
+    pub enum Operation {
+        Buy = "Buy",
+        Sell = "Sell",
+    }
+}
+
+wire_enum! {
+    pub enum OrderStatus {
+        New = "New",
+        PartiallyFill = "PartiallyFill",
+        Fill = "Fill",
+        Cancelled = "Cancelled",
+        Replaced = "Replaced",
+        PendingCancel = "PendingCancel",
+        Rejected = "Rejected",
+        PendingReplace = "PendingReplace",
+        PendingNew = "PendingNew",
+    }
+}
+
+wire_enum! {
+    pub enum OrderType {
+        Limit = "Limit",
+        Market = "Market",
+    }
+}
+
+wire_enum! {
+    pub enum InstrumentType {
+        Stock = "Stock",
+        Currency = "Currency",
+        Bond = "Bond",
+        Etf = "Etf",
+    }
+}
+
+wire_enum! {
+    pub enum TradeStatus {
+        NormalTrading = "NormalTrading",
+        NotAvailableForTrading = "NotAvailableForTrading",
+    }
+}
+
+wire_enum! {
+    pub enum BrokerAccountType {
+        Tinkoff = "Tinkoff",
+        TinkoffIis = "TinkoffIis",
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -65,26 +277,26 @@ pub struct EmptyPayload {}
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OrdersPayload {
-    pub order_id: String,
-    pub figi: String,
+    pub order_id: OrderId,
+    pub figi: Figi,
     pub operation: Operation,
     pub status: OrderStatus,
     pub requested_lots: i64,
     pub executed_lots: i64,
     pub r#type: OrderType,
-    pub price: f64,
+    pub price: Amount,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct MoneyAmount {
     pub currency: Currency,
-    pub value: f32,
+    pub value: Amount,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct LimitOrderPayload {
-    pub order_id: String,
+    pub order_id: OrderId,
     pub operation: Operation,
     pub status: OrderStatus,
     pub reject_reason: Option<String>,
@@ -97,7 +309,7 @@ pub struct LimitOrderPayload {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketOrderPayload {
-    pub order_id: String,
+    pub order_id: OrderId,
     pub operation: Operation,
     pub status: OrderStatus,
     pub reject_reason: Option<String>,
@@ -110,12 +322,12 @@ pub struct MarketOrderPayload {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Position {
-    pub figi: String,
-    pub ticker: Option<String>,
-    pub isin: Option<String>,
+    pub figi: Figi,
+    pub ticker: Option<Ticker>,
+    pub isin: Option<Isin>,
     pub instrument_type: InstrumentType,
-    pub balance: f64,
-    pub blocked: Option<f64>,
+    pub balance: Amount,
+    pub blocked: Option<Amount>,
     pub lots: i32,
     pub expected_yield: Option<MoneyAmount>,
     pub average_position_price: Option<MoneyAmount>,
@@ -132,8 +344,8 @@ pub struct PortfolioPayload {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CurrencyPosition {
     pub currency: Currency,
-    pub balance: f64,
-    pub blocked: Option<f64>,
+    pub balance: Amount,
+    pub blocked: Option<Amount>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -144,67 +356,69 @@ pub struct PortfolioCurrenciesPayload {
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct OrderBookOrder {
-    pub price: f64,
+    pub price: Amount,
     pub quantity: i32,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OrderBookPayload {
-    pub figi: String,
+    pub figi: Figi,
     pub depth: i32,
     #[serde(default)]
     pub bids: Vec<OrderBookOrder>,
     #[serde(default)]
     pub asks: Vec<OrderBookOrder>,
     pub trade_status: TradeStatus,
-    pub min_price_increment: f64,
-    pub face_value: Option<f64>,
-    pub last_price: Option<f64>,
-    pub close_price: Option<f64>,
-    pub limit_up: Option<f64>,
-    pub limit_down: Option<f64>,
-}
-
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum OperationStatus {
-    Done,
-    Decline,
-    Progress,
+    pub min_price_increment: Amount,
+    pub face_value: Option<Amount>,
+    pub last_price: Option<Amount>,
+    pub close_price: Option<Amount>,
+    pub limit_up: Option<Amount>,
+    pub limit_down: Option<Amount>,
+}
+
+wire_enum! {
+    pub enum OperationStatus {
+        Done = "Done",
+        Decline = "Decline",
+        Progress = "Progress",
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct OperationTrade {
     pub trade_id: String,
-    pub date: DateTime<Local>,
-    pub price: f64,
+    pub date: DateTime<FixedOffset>,
+    pub price: Amount,
     pub quantity: i32,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-pub enum OperationTypeWithCommission {
-    Buy,
-    BuyCard,
-    Sell,
-    BrokerCommission,
-    ExchangeCommission,
-    ServiceCommission,
-    MarginCommission,
-    OtherCommission,
-    PayIn,
-    PayOut,
-    Tax,
-    TaxLucre,
-    TaxDividend,
-    TaxCoupon,
-    TaxBack,
-    Repayment,
-    PartRepayment,
-    Coupon,
-    Dividend,
-    SecurityIn,
-    SecurityOut,
+wire_enum! {
+    pub enum OperationTypeWithCommission {
+        Buy = "Buy",
+        BuyCard = "BuyCard",
+        Sell = "Sell",
+        BrokerCommission = "BrokerCommission",
+        ExchangeCommission = "ExchangeCommission",
+        ServiceCommission = "ServiceCommission",
+        MarginCommission = "MarginCommission",
+        OtherCommission = "OtherCommission",
+        PayIn = "PayIn",
+        PayOut = "PayOut",
+        Tax = "Tax",
+        TaxLucre = "TaxLucre",
+        TaxDividend = "TaxDividend",
+        TaxCoupon = "TaxCoupon",
+        TaxBack = "TaxBack",
+        Repayment = "Repayment",
+        PartRepayment = "PartRepayment",
+        Coupon = "Coupon",
+        Dividend = "Dividend",
+        SecurityIn = "SecurityIn",
+        SecurityOut = "SecurityOut",
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -216,13 +430,13 @@ pub struct OperationItem {
     pub trades: Vec<OperationTrade>,
     pub commission: Option<MoneyAmount>,
     pub currency: Currency,
-    pub payment: f64,
-    pub price: Option<f64>,
+    pub payment: Amount,
+    pub price: Option<Amount>,
     pub quantity: Option<i32>,
-    pub figi: Option<String>,
+    pub figi: Option<Figi>,
     pub instrument_type: Option<InstrumentType>,
     pub is_margin_call: bool,
-    pub date: DateTime<Local>,
+    pub date: DateTime<FixedOffset>,
     pub operation_type: Option<OperationTypeWithCommission>,
 }
 
@@ -236,7 +450,7 @@ pub struct OperationsPayload {
 #[serde(rename_all = "camelCase")]
 pub struct UserAccount {
     pub broker_account_type: BrokerAccountType,
-    pub broker_account_id: String,
+    pub broker_account_id: BrokerAccountId,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -245,62 +459,37 @@ pub struct AccountsPayload {
     pub accounts: Vec<UserAccount>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
-#[serde(rename_all = "camelCase")]
-pub enum Interval {
-    #[serde(rename = "1min")]
-    _1min,
-    #[serde(rename = "2min")]
-    _2min,
-    #[serde(rename = "3min")]
-    _3min,
-    #[serde(rename = "5min")]
-    _5min,
-    #[serde(rename = "10min")]
-    _10min,
-    #[serde(rename = "15min")]
-    _15min,
-    #[serde(rename = "30min")]
-    _30min,
-    Hour,
-    Day,
-    Week,
-    Month,
-}
-
-impl std::fmt::Display for Interval {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Interval::_1min => write!(f, "1min"),
-            Interval::_2min => write!(f, "2min"),
-            Interval::_3min => write!(f, "3min"),
-            Interval::_5min => write!(f, "5min"),
-            Interval::_10min => write!(f, "10min"),
-            Interval::_15min => write!(f, "15min"),
-            Interval::_30min => write!(f, "30min"),
-            Interval::Hour => write!(f, "Hour"),
-            Interval::Day => write!(f, "Day"),
-            Interval::Week => write!(f, "Week"),
-            Interval::Month => write!(f, "Month"),
-        }
+wire_enum! {
+    pub enum Interval {
+        _1min = "1min",
+        _2min = "2min",
+        _3min = "3min",
+        _5min = "5min",
+        _10min = "10min",
+        _15min = "15min",
+        _30min = "30min",
+        Hour = "hour",
+        Day = "day",
+        Week = "week",
+        Month = "month",
     }
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct Candle {
-    pub figi: String,
+    pub figi: Figi,
     pub interval: Interval,
-    pub o: f64,
-    pub c: f64,
-    pub h: f64,
-    pub l: f64,
+    pub o: Amount,
+    pub c: Amount,
+    pub h: Amount,
+    pub l: Amount,
     pub v: i32,
-    pub time: DateTime<Local>,
+    pub time: DateTime<FixedOffset>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CandlesPayload {
-    pub figi: String,
+    pub figi: Figi,
     pub interval: Interval,
     #[serde(default)]
     pub candles: Vec<Candle>,
@@ -309,10 +498,10 @@ pub struct CandlesPayload {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SearchMarketInstrumentPayload {
-    pub figi: String,
-    pub ticker: String,
-    pub isin: Option<String>,
-    pub min_price_increment: Option<f64>,
+    pub figi: Figi,
+    pub ticker: Ticker,
+    pub isin: Option<Isin>,
+    pub min_price_increment: Option<Amount>,
     pub lot: i32,
     pub currency: Option<Currency>,
     pub name: String,
@@ -322,10 +511,10 @@ pub struct SearchMarketInstrumentPayload {
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct MarketInstrument {
-    pub figi: String,
-    pub ticker: String,
-    pub isin: Option<String>,
-    pub min_price_increment: Option<f64>,
+    pub figi: Figi,
+    pub ticker: Ticker,
+    pub isin: Option<Isin>,
+    pub min_price_increment: Option<Amount>,
     pub lot: i32,
     pub currency: Option<Currency>,
     pub name: String,
@@ -348,11 +537,13 @@ pub enum SandboxRequest {
     },
     SetCurrenciesBalance {
         currency: Currency,
-        balance: f64,
+        #[cfg_attr(feature = "decimal", serde(with = "rust_decimal::serde::float"))]
+        balance: Amount,
     },
     SetPositionBalance {
-        figi: String,
-        balance: f64,
+        figi: Figi,
+        #[cfg_attr(feature = "decimal", serde(with = "rust_decimal::serde::float"))]
+        balance: Amount,
     },
 }
 
@@ -360,7 +551,7 @@ pub enum SandboxRequest {
 #[serde(rename_all = "camelCase")]
 pub struct SandboxAccount {
     pub broker_account_type: BrokerAccountType,
-    pub broker_account_id: String,
+    pub broker_account_id: BrokerAccountId,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -370,7 +561,8 @@ pub enum OrderRequest {
     MakeLimitOrder {
         operation: Operation,
         lots: i32,
-        price: f64,
+        #[cfg_attr(feature = "decimal", serde(with = "rust_decimal::serde::float"))]
+        price: Amount,
     },
     MakeMarketOrder {
         operation: Operation,
@@ -384,131 +576,140 @@ pub enum Status {
     Error,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ErrorPayload {
+    pub message: Option<String>,
+    pub code: Option<String>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct Response<T> {
-    pub tracking_id: String,
+    pub tracking_id: TrackingId,
     pub status: Status,
     pub payload: T,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "event")]
-pub enum OutcomeEvent {
-    #[serde(rename = "candle:subscribe")]
-    CandleSubscribe {
-        figi: String,
-        interval: Interval,
-        request_id: Option<String>,
-    },
-    #[serde(rename = "candle:unsubscribe")]
-    CandleUnsubscribe {
-        figi: String,
-        interval: Interval,
-        request_id: Option<String>,
-    },
-    #[serde(rename = "candle:unsubscribe")]
-    OrderbookSubscribe {
-        figi: String,
-        depth: i32,
-        request_id: Option<String>,
-    },
-    #[serde(rename = "orderbook:unsubscribe")]
-    OrderbookUnsubscribe {
-        figi: String,
-        depth: i32,
-        request_id: Option<String>,
-    },
-    #[serde(rename = "instrument_info:subscribe")]
-    InstrumentInfoSubscribe {
-        figi: String,
-        request_id: Option<String>,
-    },
-    #[serde(rename = "instrument_info:unsubscribe")]
-    InstrumentInfoUnsubscribe {
-        figi: String,
-        request_id: Option<String>,
-    },
-    #[serde(skip_serializing)]
-    #[serde(skip_deserializing)]
-    Ping(#[serde(default)] Vec<u8>),
-    #[serde(skip_serializing)]
-    #[serde(skip_deserializing)]
-    Pong(#[serde(default)] Vec<u8>),
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-#[serde(tag = "event")]
-pub enum IncomeEvent {
-    #[serde(rename = "candle")]
-    Candle {
-        time: DateTime<Local>,
-        payload: CandleEventPayload,
-    },
-    #[serde(rename = "orderbook")]
-    OrderBook {
-        time: DateTime<Local>,
-        payload: OrderBookPayload,
-    },
-    #[serde(rename = "instrument_info")]
-    InstrumentInfo {
-        time: DateTime<Local>,
-        payload: InstrumentInfoEventPayload,
-    },
-    #[serde(rename = "error")]
-    Error {
-        time: DateTime<Local>,
-        payload: ErrorEventPayload,
-    },
-    #[serde(skip_serializing)]
-    #[serde(skip_deserializing)]
-    Binary(#[serde(default)] Vec<u8>),
-    #[serde(skip_serializing)]
-    #[serde(skip_deserializing)]
-    Ping(#[serde(default)] Vec<u8>),
-    #[serde(skip_serializing)]
-    #[serde(skip_deserializing)]
-    Pong(#[serde(default)] Vec<u8>),
-    #[serde(skip_serializing)]
-    #[serde(skip_deserializing)]
-    Close,
-}
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+
+    #[test]
+    fn unknown_enum_values() {
+        let payload: OperationsPayload = serde_json::from_str(
+            "{
+                \"operations\": [
+                    {
+                        \"id\": \"operation_0\",
+                        \"status\": \"Done\",
+                        \"currency\": \"KZT\",
+                        \"payment\": -10.5,
+                        \"instrumentType\": \"Futures\",
+                        \"isMarginCall\": false,
+                        \"date\": \"2021-11-01T10:00:00+03:00\",
+                        \"operationType\": \"AccruedInterest\"
+                    }
+                ]
+            }",
+        )
+        .unwrap();
+
+        let operation = &payload.operations[0];
+        assert_eq!(operation.status, OperationStatus::Done);
+        assert_eq!(operation.currency, Currency::Unknown("KZT".to_string()));
+        assert_eq!(
+            operation.instrument_type,
+            Some(InstrumentType::Unknown("Futures".to_string()))
+        );
+        assert_eq!(
+            operation.operation_type,
+            Some(OperationTypeWithCommission::Unknown(
+                "AccruedInterest".to_string()
+            ))
+        );
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct CandleEventPayload {
-    pub o: f64,
-    pub c: f64,
-    pub h: f64,
-    pub l: f64,
-    pub v: f64,
-    pub time: DateTime<Local>,
-    pub interval: Interval,
-    pub figi: String,
-}
+    #[test]
+    fn wire_values() {
+        assert_eq!(serde_json::to_string(&Interval::_1min).unwrap(), "\"1min\"");
+        assert_eq!(Interval::Hour.to_string(), "hour");
+        assert_eq!(
+            serde_json::from_str::<Interval>("\"hour\"").unwrap(),
+            Interval::Hour
+        );
+
+        let unknown = BrokerAccountType::Unknown("TinkoffPension".to_string());
+        let json = serde_json::to_string(&unknown).unwrap();
+        assert_eq!(json, "\"TinkoffPension\"");
+        assert_eq!(
+            serde_json::from_str::<BrokerAccountType>(&json).unwrap(),
+            unknown
+        );
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct OrderBookEventPayload {
-    pub figi: String,
-    pub depth: i32,
-    #[serde(default)]
-    pub bids: Vec<(f64, f64)>,
-    #[serde(default)]
-    pub asks: Vec<(f64, f64)>,
-}
+    #[cfg(feature = "decimal")]
+    #[test]
+    fn exact_amounts() {
+        let amount: MoneyAmount =
+            serde_json::from_str("{\"currency\": \"RUB\", \"value\": 12345678901234.57}").unwrap();
+        assert_eq!(amount.value, "12345678901234.57".parse().unwrap());
+
+        let request = OrderRequest::MakeLimitOrder {
+            operation: Operation::Buy,
+            lots: 1,
+            price: "0.1000001".parse().unwrap(),
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            "{\"operation\":\"Buy\",\"lots\":1,\"price\":0.1000001}"
+        );
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct InstrumentInfoEventPayload {
-    pub trade_status: String,
-    pub min_price_increment: f64,
-    pub lot: f64,
-    pub accrued_interest: Option<f64>,
-    pub limit_up: Option<f64>,
-    pub limit_down: Option<f64>,
-    pub figi: String,
-}
+    #[test]
+    fn identifiers() {
+        let figi = Figi::new("BBG000B9XRY4").unwrap();
+        assert_eq!(figi, "BBG000B9XRY4");
+        assert_eq!(serde_json::to_string(&figi).unwrap(), "\"BBG000B9XRY4\"");
+
+        assert!(Figi::new("").is_err());
+        assert!(Figi::new("figi_0").is_err());
+        assert!(Figi::new("BBG000b9xry4").is_err());
+        assert!(Figi::new("XYZ000B9XRY4").is_err());
+        assert!(Isin::new("US0378331005").is_ok());
+        assert!(Isin::new("0378331005US").is_err());
+        assert!(Isin::new("US037833100").is_err());
+        assert!(Ticker::new("figi_0").is_ok());
+        assert!("order 0".parse::<OrderId>().is_err());
+
+        let instrument: MarketInstrument = serde_json::from_str(
+            "{
+                \"figi\": \"BBG0013HGFT4\",
+                \"ticker\": \"USD000UTSTOM\",
+                \"isin\": \"\",
+                \"lot\": 1000,
+                \"currency\": \"RUB\",
+                \"name\": \"US Dollar\",
+                \"type\": \"Currency\"
+            }",
+        )
+        .unwrap();
+        assert_eq!(instrument.isin.unwrap(), "");
+    }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct ErrorEventPayload {
-    pub error: String,
-    pub request_id: Option<String>,
+    #[test]
+    fn wire_offset() {
+        let trade: OperationTrade = serde_json::from_str(
+            "{
+                \"tradeId\": \"trade_0\",
+                \"date\": \"2020-02-01T18:38:33.131642+03:00\",
+                \"price\": 123.45,
+                \"quantity\": 1
+            }",
+        )
+        .unwrap();
+
+        assert_eq!(trade.date.offset(), &crate::moscow::offset());
+        assert_eq!(trade.date.to_rfc3339(), "2020-02-01T18:38:33.131642+03:00");
+    }
 }
@@ -1,11 +1,47 @@
+use crate::domain::TrackingId;
 use std::error;
 use std::fmt;
+use tokio_tungstenite::tungstenite;
+
+/// Broker error codes reporting that the account lacks money or assets for the operation.
+const INSUFFICIENT_FUNDS_CODES: &[&str] = &["NOT_ENOUGH_BALANCE", "INSUFFICIENT_BALANCE"];
+
+/// Broker error codes reporting that a well-formed request was rejected by business rules.
+const REJECTION_CODES: &[&str] = &[
+    "ORDER_ERROR",
+    "INSTRUMENT_NOT_TRADABLE",
+    "MARKET_CLOSED",
+    "NOT_AVAILABLE_FOR_TRADING",
+];
+
+/// Coarse classification of an [`Error`], independent of the transport it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// Connection failures, timeouts and dropped sockets.
+    Network,
+    /// Token is missing, invalid or lacks permissions (HTTP 401/403).
+    Auth,
+    /// Request quota exceeded (HTTP 429).
+    RateLimited,
+    /// Request was malformed or referenced something that does not exist (other HTTP 4xx).
+    Validation,
+    /// Not enough money or assets on the account to complete the operation.
+    InsufficientFunds,
+    /// Request was understood but rejected by the broker, e.g. the instrument is not tradable.
+    Rejected,
+    /// Broker side failure (HTTP 5xx).
+    Server,
+    /// Payload could not be encoded or decoded.
+    Serialization,
+    /// Anything that does not fit the categories above.
+    Other,
+}
 
 #[derive(Debug)]
 pub enum Error {
     ServiceError {
-        http_code: u8,
-        tracking_id: String,
+        http_code: u16,
+        tracking_id: TrackingId,
         code: String,
         message: String,
     },
@@ -30,11 +66,108 @@ pub enum Error {
         cause: serde_json::error::Error,
     },
 
+    UnexpectedResponseError {
+        http_code: u16,
+        text: String,
+    },
+
+    ConfigurationError {
+        description: String,
+    },
+
+    ValidationError {
+        description: String,
+    },
+
+    StaleConnectionError {
+        description: String,
+    },
+
+    SubscriptionError {
+        request_id: String,
+        description: String,
+    },
+
     GeneralError {
         description: String,
     },
 }
 
+impl Error {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            Error::ServiceError {
+                http_code, code, ..
+            } => {
+                if INSUFFICIENT_FUNDS_CODES.contains(&code.as_str()) {
+                    ErrorKind::InsufficientFunds
+                } else if REJECTION_CODES.contains(&code.as_str()) {
+                    ErrorKind::Rejected
+                } else {
+                    kind_from_http_code(*http_code)
+                }
+            }
+
+            Error::HTTPClientError { cause, .. } => {
+                if cause.is_decode() {
+                    ErrorKind::Serialization
+                } else if let Some(status) = cause.status() {
+                    kind_from_http_code(status.as_u16())
+                } else if cause.is_builder() {
+                    ErrorKind::Other
+                } else {
+                    ErrorKind::Network
+                }
+            }
+
+            Error::WSHTTPClientError { .. } => ErrorKind::Other,
+
+            Error::WSClientError { cause, .. } => match cause {
+                tungstenite::Error::ConnectionClosed
+                | tungstenite::Error::AlreadyClosed
+                | tungstenite::Error::Io(_)
+                | tungstenite::Error::Tls(_) => ErrorKind::Network,
+                tungstenite::Error::Http(response) => {
+                    kind_from_http_code(response.status().as_u16())
+                }
+                _ => ErrorKind::Other,
+            },
+
+            Error::SerializationError { .. } => ErrorKind::Serialization,
+
+            Error::UnexpectedResponseError { http_code, .. } => kind_from_http_code(*http_code),
+
+            Error::ConfigurationError { .. } => ErrorKind::Other,
+
+            Error::ValidationError { .. } => ErrorKind::Validation,
+
+            Error::StaleConnectionError { .. } => ErrorKind::Network,
+
+            Error::SubscriptionError { .. } => ErrorKind::Rejected,
+
+            Error::GeneralError { .. } => ErrorKind::Other,
+        }
+    }
+
+    /// Whether repeating the same request later has a reasonable chance to succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self.kind(),
+            ErrorKind::Network | ErrorKind::RateLimited | ErrorKind::Server
+        )
+    }
+}
+
+fn kind_from_http_code(http_code: u16) -> ErrorKind {
+    match http_code {
+        401 | 403 => ErrorKind::Auth,
+        429 => ErrorKind::RateLimited,
+        400..=499 => ErrorKind::Validation,
+        500..=599 => ErrorKind::Server,
+        _ => ErrorKind::Other,
+    }
+}
+
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
@@ -65,6 +198,22 @@ impl error::Error for Error {
                 cause: cause,
             } => Some(cause),
 
+            Error::UnexpectedResponseError {
+                http_code: _,
+                text: _,
+            } => None,
+
+            Error::ConfigurationError { description: _ } => None,
+
+            Error::ValidationError { description: _ } => None,
+
+            Error::StaleConnectionError { description: _ } => None,
+
+            Error::SubscriptionError {
+                request_id: _,
+                description: _,
+            } => None,
+
             Error::GeneralError { description: _ } => None,
         }
     }
@@ -98,6 +247,28 @@ impl error::Error for Error {
                 cause: _,
             } => description,
 
+            Error::UnexpectedResponseError {
+                http_code: _,
+                text: text,
+            } => text,
+
+            Error::ConfigurationError {
+                description: description,
+            } => description,
+
+            Error::ValidationError {
+                description: description,
+            } => description,
+
+            Error::StaleConnectionError {
+                description: description,
+            } => description,
+
+            Error::SubscriptionError {
+                request_id: _,
+                description: description,
+            } => description,
+
             Error::GeneralError {
                 description: description,
             } => description,
@@ -155,6 +326,33 @@ impl fmt::Display for Error {
                 description, cause
             ),
 
+            Error::UnexpectedResponseError { http_code, text } => write!(
+                f,
+                "UnexpectedResponseError(http_code={}, text={})",
+                http_code, text
+            ),
+
+            Error::ConfigurationError { description } => {
+                write!(f, "ConfigurationError(description={})", description)
+            }
+
+            Error::ValidationError { description } => {
+                write!(f, "ValidationError(description={})", description)
+            }
+
+            Error::StaleConnectionError { description } => {
+                write!(f, "StaleConnectionError(description={})", description)
+            }
+
+            Error::SubscriptionError {
+                request_id,
+                description,
+            } => write!(
+                f,
+                "SubscriptionError(request_id={}, description={})",
+                request_id, description
+            ),
+
             Error::GeneralError { description } => {
                 write!(f, "GeneralError(description={})", description)
             }
@@ -197,3 +395,58 @@ impl From<tokio_tungstenite::tungstenite::Error> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::TrackingId;
+    use crate::errors::{Error, ErrorKind};
+
+    fn service_error(http_code: u16, code: &str) -> Error {
+        Error::ServiceError {
+            http_code,
+            tracking_id: TrackingId::new("tracking_id_0").unwrap(),
+            code: code.to_string(),
+            message: "message".to_string(),
+        }
+    }
+
+    #[test]
+    fn service_error_kind() {
+        assert_eq!(service_error(401, "").kind(), ErrorKind::Auth);
+        assert_eq!(service_error(403, "").kind(), ErrorKind::Auth);
+        assert_eq!(service_error(429, "").kind(), ErrorKind::RateLimited);
+        assert_eq!(
+            service_error(400, "VALIDATION_ERROR").kind(),
+            ErrorKind::Validation
+        );
+        assert_eq!(
+            service_error(500, "NOT_ENOUGH_BALANCE").kind(),
+            ErrorKind::InsufficientFunds
+        );
+        assert_eq!(
+            service_error(500, "ORDER_ERROR").kind(),
+            ErrorKind::Rejected
+        );
+        assert_eq!(service_error(503, "").kind(), ErrorKind::Server);
+    }
+
+    #[test]
+    fn retryable() {
+        assert!(service_error(429, "").is_retryable());
+        assert!(service_error(502, "").is_retryable());
+        assert!(!service_error(500, "NOT_ENOUGH_BALANCE").is_retryable());
+        assert!(!service_error(400, "").is_retryable());
+
+        let unexpected = Error::UnexpectedResponseError {
+            http_code: 504,
+            text: "Gateway Timeout".to_string(),
+        };
+        assert_eq!(unexpected.kind(), ErrorKind::Server);
+        assert!(unexpected.is_retryable());
+
+        let serialization = Error::from(serde_json::from_str::<u8>("x").unwrap_err());
+        assert_eq!(serialization.kind(), ErrorKind::Serialization);
+        assert!(!serialization.is_retryable());
+    }
+}
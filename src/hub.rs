@@ -0,0 +1,469 @@
+use crate::domain::*;
+use crate::errors::Error;
+use crate::session::{SessionEvent, StreamingSession};
+use crate::subscription::SubscriptionStream;
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::task::{Context, Poll};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Item of a [`HubStream`].
+#[derive(Debug)]
+pub enum HubEvent<T> {
+    Event(Arc<T>),
+    /// The subscriber fell behind and this many events were skipped. The stream
+    /// continues with the oldest event still buffered.
+    Lagged(u64),
+}
+
+/// Market data shared by many consumers over one [`StreamingSession`].
+///
+/// The hub keeps a single upstream subscription per instrument and channel, no
+/// matter how many subscribers it has, and unsubscribes once the last subscriber
+/// is dropped. Every subscriber gets its own [`HubStream`] buffering up to
+/// `capacity` events, so a slow one lags behind with [`HubEvent::Lagged`] instead
+/// of holding up the others.
+///
+/// The hub is cheap to clone and hand out to components.
+#[derive(Clone)]
+pub struct MarketDataHub {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    session: StreamingSession,
+    capacity: usize,
+    events: broadcast::Sender<Arc<SessionEvent>>,
+    candles: Mutex<HashMap<(Figi, Interval), Channel<CandleEventPayload>>>,
+    order_books: Mutex<HashMap<(Figi, i32), Channel<OrderBookEventPayload>>>,
+    instrument_info: Mutex<HashMap<Figi, Channel<InstrumentInfoEventPayload>>>,
+    next_generation: AtomicU64,
+}
+
+struct Channel<T> {
+    sender: broadcast::Sender<Arc<T>>,
+    subscribers: usize,
+    generation: u64,
+    upstream: JoinHandle<()>,
+}
+
+type Channels<K, T> = Mutex<HashMap<K, Channel<T>>>;
+
+impl MarketDataHub {
+    /// Takes over `session`. Its connection events are available from [`events`](Self::events).
+    pub fn new(mut session: StreamingSession, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let (events, _) = broadcast::channel(capacity);
+
+        let mut session_events = session.take_events();
+        let sender = events.clone();
+        tokio::spawn(async move {
+            while let Some(event) = session_events.recv().await {
+                let _ = sender.send(Arc::new(event));
+            }
+        });
+
+        Self {
+            inner: Arc::new(Inner {
+                session: session,
+                capacity: capacity,
+                events: events,
+                candles: Mutex::new(HashMap::new()),
+                order_books: Mutex::new(HashMap::new()),
+                instrument_info: Mutex::new(HashMap::new()),
+                next_generation: AtomicU64::new(0),
+            }),
+        }
+    }
+
+    /// Session events that aren't market data, e.g. disconnects and rejected subscriptions.
+    pub fn events(&self) -> HubStream<SessionEvent> {
+        HubStream::new(self.inner.events.subscribe(), None)
+    }
+
+    pub fn subscribe_candles(
+        &self,
+        figi: &Figi,
+        interval: Interval,
+    ) -> Result<HubStream<CandleEventPayload>, Error> {
+        let key = (figi.clone(), interval.clone());
+
+        subscribe(
+            &self.inner,
+            |inner| &inner.candles,
+            key,
+            |session| session.subscribe_candles(figi, interval),
+        )
+    }
+
+    pub fn subscribe_orderbook(
+        &self,
+        figi: &Figi,
+        depth: i32,
+    ) -> Result<HubStream<OrderBookEventPayload>, Error> {
+        subscribe(
+            &self.inner,
+            |inner| &inner.order_books,
+            (figi.clone(), depth),
+            |session| session.subscribe_orderbook(figi, depth),
+        )
+    }
+
+    pub fn subscribe_instrument_info(
+        &self,
+        figi: &Figi,
+    ) -> Result<HubStream<InstrumentInfoEventPayload>, Error> {
+        subscribe(
+            &self.inner,
+            |inner| &inner.instrument_info,
+            figi.clone(),
+            |session| session.subscribe_instrument_info(figi),
+        )
+    }
+
+    /// Number of subscribers of each upstream candle subscription.
+    pub fn candle_subscribers(&self) -> HashMap<(Figi, Interval), usize> {
+        subscribers(&self.inner.candles)
+    }
+
+    /// Number of subscribers of each upstream order book subscription.
+    pub fn orderbook_subscribers(&self) -> HashMap<(Figi, i32), usize> {
+        subscribers(&self.inner.order_books)
+    }
+
+    /// Number of subscribers of each upstream instrument info subscription.
+    pub fn instrument_info_subscribers(&self) -> HashMap<Figi, usize> {
+        subscribers(&self.inner.instrument_info)
+    }
+}
+
+/// Joins the channel of `key`, subscribing upstream for its first subscriber.
+fn subscribe<K, T>(
+    inner: &Arc<Inner>,
+    channels: fn(&Inner) -> &Channels<K, T>,
+    key: K,
+    upstream: impl FnOnce(&StreamingSession) -> Result<SubscriptionStream<T>, Error>,
+) -> Result<HubStream<T>, Error>
+where
+    K: Eq + Hash + Clone + Send + 'static,
+    T: Send + Sync + 'static,
+{
+    let mut locked = channels(inner).lock().unwrap();
+
+    if !locked.contains_key(&key) {
+        let upstream = upstream(&inner.session)?;
+        let (sender, _) = broadcast::channel(inner.capacity);
+        let generation = inner.next_generation.fetch_add(1, Ordering::Relaxed);
+
+        let forward = forward(
+            upstream,
+            sender.clone(),
+            Arc::downgrade(inner),
+            channels,
+            key.clone(),
+            generation,
+        );
+
+        locked.insert(
+            key.clone(),
+            Channel {
+                sender: sender,
+                subscribers: 0,
+                generation: generation,
+                upstream: tokio::spawn(forward),
+            },
+        );
+    }
+
+    let channel = locked.get_mut(&key).unwrap();
+    channel.subscribers += 1;
+    let receiver = channel.sender.subscribe();
+    let generation = channel.generation;
+
+    let inner = inner.clone();
+    let release = move || {
+        let mut locked = channels(&inner).lock().unwrap();
+
+        if let Some(channel) = locked.get_mut(&key) {
+            if channel.generation == generation {
+                channel.subscribers -= 1;
+                if channel.subscribers == 0 {
+                    // Dropping the upstream stream unsubscribes.
+                    locked.remove(&key).unwrap().upstream.abort();
+                }
+            }
+        }
+    };
+
+    Ok(HubStream::new(receiver, Some(Box::new(release))))
+}
+
+/// Broadcasts the upstream events. When the upstream ends, e.g. because the subscription
+/// was rejected, the channel is removed so that its subscribers end too.
+async fn forward<K, T>(
+    mut upstream: SubscriptionStream<T>,
+    sender: broadcast::Sender<Arc<T>>,
+    inner: Weak<Inner>,
+    channels: fn(&Inner) -> &Channels<K, T>,
+    key: K,
+    generation: u64,
+) where
+    K: Eq + Hash,
+{
+    while let Some(event) = upstream.next().await {
+        // Fails only while no subscriber is listening.
+        let _ = sender.send(Arc::new(event));
+    }
+
+    if let Some(inner) = inner.upgrade() {
+        let mut locked = channels(&inner).lock().unwrap();
+
+        if locked.get(&key).map(|c| c.generation) == Some(generation) {
+            locked.remove(&key);
+        }
+    }
+}
+
+fn subscribers<K: Clone + Eq + Hash, T>(channels: &Channels<K, T>) -> HashMap<K, usize> {
+    channels
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(key, channel)| (key.clone(), channel.subscribers))
+        .collect()
+}
+
+/// Subscriber of a [`MarketDataHub`]. Dropping it leaves the channel.
+pub struct HubStream<T> {
+    events: BoxStream<'static, HubEvent<T>>,
+    release: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl<T: Send + Sync + 'static> HubStream<T> {
+    fn new(
+        receiver: broadcast::Receiver<Arc<T>>,
+        release: Option<Box<dyn FnOnce() + Send>>,
+    ) -> Self {
+        let events = stream::unfold(receiver, |mut receiver| async move {
+            match receiver.recv().await {
+                Ok(event) => Some((HubEvent::Event(event), receiver)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    Some((HubEvent::Lagged(skipped), receiver))
+                }
+                Err(broadcast::error::RecvError::Closed) => None,
+            }
+        });
+
+        Self {
+            events: events.boxed(),
+            release: release,
+        }
+    }
+}
+
+impl<T> Stream for HubStream<T> {
+    type Item = HubEvent<T>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<HubEvent<T>>> {
+        self.events.poll_next_unpin(cx)
+    }
+}
+
+impl<T> Drop for HubStream<T> {
+    fn drop(&mut self) {
+        if let Some(release) = self.release.take() {
+            release();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::hub::{HubEvent, MarketDataHub};
+    use crate::{Environment, SessionConfig, TinkoffInvestClient};
+    use futures::{SinkExt, StreamExt};
+    use std::net::SocketAddr;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use tokio_tungstenite::accept_async;
+    use tokio_tungstenite::tungstenite::Message;
+
+    async fn hub(address: SocketAddr, capacity: usize) -> MarketDataHub {
+        let session = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: "http://127.0.0.1:1".to_string(),
+                streaming: format!("ws://{}", address),
+            })
+            .build()
+            .unwrap()
+            .streaming_session(SessionConfig::default().keepalive(false))
+            .await
+            .unwrap();
+
+        MarketDataHub::new(session, capacity)
+    }
+
+    fn candle(open: u32) -> Message {
+        Message::text(format!(
+            "{{\"event\": \"candle\", \"time\": \"2021-11-01T10:00:00+03:00\", \
+             \"payload\": {{\"o\": {}, \"c\": 1.0, \"h\": 1.0, \"l\": 1.0, \"v\": 10, \
+             \"time\": \"2021-11-01T10:00:00+03:00\", \"interval\": \"1min\", \"figi\": \"BBG000B9XRY4\"}}}}",
+            open
+        ))
+    }
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    fn open(event: HubEvent<CandleEventPayload>) -> Amount {
+        match event {
+            HubEvent::Event(candle) => candle.o,
+            HubEvent::Lagged(skipped) => panic!("lagged by {}", skipped),
+        }
+    }
+
+    /// Server sending `candles` after the first message and reporting every message it gets.
+    async fn server(candles: u32) -> (SocketAddr, mpsc::UnboundedReceiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (received, messages) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+
+            let subscription = ws.next().await.unwrap().unwrap();
+            received.send(subscription.into_text().unwrap()).unwrap();
+            for i in 1..=candles {
+                ws.send(candle(i)).await.unwrap();
+            }
+
+            while let Some(Ok(message)) = ws.next().await {
+                if let Ok(text) = message.into_text() {
+                    received.send(text).unwrap();
+                }
+            }
+        });
+
+        (address, messages)
+    }
+
+    #[tokio::test]
+    async fn shares_upstream_subscription() {
+        let (address, mut messages) = server(1).await;
+        let hub = hub(address, 16).await;
+        let figi = Figi::new("BBG000B9XRY4").unwrap();
+
+        let mut first = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+        let mut second = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+        assert_eq!(
+            hub.candle_subscribers()[&(figi.clone(), Interval::_1min)],
+            2
+        );
+
+        assert_eq!(open(first.next().await.unwrap()), amount("1"));
+        assert_eq!(open(second.next().await.unwrap()), amount("1"));
+        assert!(messages.recv().await.unwrap().contains("candle:subscribe"));
+
+        drop(first);
+        assert_eq!(
+            hub.candle_subscribers()[&(figi.clone(), Interval::_1min)],
+            1
+        );
+
+        drop(second);
+        assert!(hub.candle_subscribers().is_empty());
+        assert!(messages
+            .recv()
+            .await
+            .unwrap()
+            .contains("candle:unsubscribe"));
+    }
+
+    #[tokio::test]
+    async fn reports_lagging_subscriber() {
+        let (address, _messages) = server(4).await;
+        let hub = hub(address, 2).await;
+        let figi = Figi::new("BBG000B9XRY4").unwrap();
+
+        let mut slow = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+        let mut fast = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+
+        // Once the fast subscriber has the last candle, all of them were broadcast.
+        while let Some(event) = fast.next().await {
+            if matches!(&event, HubEvent::Event(candle) if candle.o == amount("4")) {
+                break;
+            }
+        }
+
+        assert!(matches!(slow.next().await, Some(HubEvent::Lagged(2))));
+        assert_eq!(open(slow.next().await.unwrap()), amount("3"));
+        assert_eq!(open(slow.next().await.unwrap()), amount("4"));
+    }
+
+    #[tokio::test]
+    async fn resubscribes_after_rejection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (received, mut messages) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+
+            let rejected = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            let request_id =
+                serde_json::from_str::<serde_json::Value>(&rejected).unwrap()["request_id"].clone();
+            received.send(rejected).unwrap();
+            ws.send(Message::text(format!(
+                "{{\"event\": \"error\", \"time\": \"2021-11-01T10:00:00+03:00\", \
+                 \"payload\": {{\"error\": \"Unknown figi\", \"request_id\": {}}}}}",
+                request_id
+            )))
+            .await
+            .unwrap();
+
+            let accepted = ws.next().await.unwrap().unwrap();
+            received.send(accepted.into_text().unwrap()).unwrap();
+            ws.send(candle(1)).await.unwrap();
+
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let hub = hub(address, 16).await;
+        let figi = Figi::new("BBG000B9XRY4").unwrap();
+        let key = (figi.clone(), Interval::_1min);
+
+        // The rejection ends every subscriber of the channel.
+        let mut first = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+        let mut second = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+        assert!(first.next().await.is_none());
+        assert!(second.next().await.is_none());
+        assert!(hub.candle_subscribers().is_empty());
+
+        // A later subscriber gets a new upstream subscription, which the ended
+        // subscribers don't release when they're dropped.
+        let mut third = hub.subscribe_candles(&figi, Interval::_1min).unwrap();
+        drop(first);
+        drop(second);
+        assert_eq!(hub.candle_subscribers()[&key], 1);
+
+        assert_eq!(open(third.next().await.unwrap()), amount("1"));
+
+        let rejected = messages.recv().await.unwrap();
+        let accepted = messages.recv().await.unwrap();
+        assert!(accepted.contains("candle:subscribe"));
+        assert_ne!(
+            serde_json::from_str::<serde_json::Value>(&accepted).unwrap()["request_id"],
+            serde_json::from_str::<serde_json::Value>(&rejected).unwrap()["request_id"]
+        );
+    }
+}
@@ -1,50 +1,189 @@
-#![allow(clippy::redundant_field_names, non_shorthand_field_patterns)]
+#![allow(
+    clippy::redundant_field_names,
+    clippy::result_large_err,
+    non_shorthand_field_patterns
+)]
 
+#[macro_use]
+mod delegate;
+
+mod account;
+mod batch;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+mod builder;
 pub mod domain;
 mod errors;
+mod hub;
 mod market;
+mod middleware;
+pub mod moscow;
 mod operations;
 mod orders;
 mod portfolio;
+mod production;
+pub mod protocol;
+mod rate_limit;
+mod read_only;
+mod retry;
 mod sandbox;
+mod session;
+mod subscription;
+#[cfg(feature = "metrics")]
+pub mod telemetry;
+mod token;
+mod transport;
 mod user;
 
+pub use crate::account::AccountClient;
+pub use crate::batch::MarketBatch;
+pub use crate::builder::{
+    Environment, TinkoffInvestClientBuilder, PRODUCTION_ENDPOINT, SANDBOX_ENDPOINT,
+    STREAMING_ENDPOINT,
+};
 use crate::domain::*;
-pub use crate::errors::Error;
-pub use crate::market::Market;
-pub use crate::operations::Operations;
+pub use crate::errors::{Error, ErrorKind};
+pub use crate::hub::{HubEvent, HubStream, MarketDataHub};
+pub use crate::market::{Market, MarketExt};
+pub use crate::middleware::{Interceptor, ResponseInfo};
+pub use crate::operations::{Operations, OperationsExt};
 pub use crate::orders::Orders;
 pub use crate::portfolio::Portfolio;
-pub use crate::sandbox::Sandbox;
+pub use crate::production::ProductionClient;
+pub use crate::rate_limit::{EndpointGroup, Quota, RateLimiter, RateLimiterState};
+pub use crate::read_only::ReadOnlyClient;
+use crate::retry::parse_retry_after;
+pub use crate::retry::RetryPolicy;
+pub use crate::sandbox::{Sandbox, SandboxClient};
+use crate::session::Connector;
+pub use crate::session::{SessionConfig, SessionEvent, StreamingSession};
+pub use crate::subscription::{
+    Acknowledgement, Subscription, SubscriptionState, SubscriptionStream,
+};
+pub use crate::token::{SecretToken, TokenScope};
+pub use crate::transport::{HttpRequest, HttpResponse, HttpTransport, ReqwestTransport};
 pub use crate::user::User;
 use futures::future;
 use futures::sink::Sink;
 use futures::stream::Stream;
 use futures_util::sink::SinkExt;
 use futures_util::StreamExt;
-use reqwest::{Client, StatusCode};
+use reqwest::header::{AUTHORIZATION, RETRY_AFTER};
+use reqwest::{Client, Method};
 use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
 use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
 
+/// `Authorization` value shown to interceptors in place of the token.
+const REDACTED_BEARER: &str = "Bearer ***";
+
 pub struct TinkoffInvestClient {
-    http_client: Client,
+    transport: Arc<dyn HttpTransport>,
     endpoint: String,
-    token: String,
+    ws_endpoint: String,
+    token: Arc<RwLock<SecretToken>>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    interceptors: Vec<Arc<dyn Interceptor>>,
 }
 
-impl<'a> TinkoffInvestClient {
+impl TinkoffInvestClient {
     pub fn new(http_client: Client, endpoint: &str, token: &str) -> Self {
         Self {
-            http_client: http_client,
+            transport: Arc::new(ReqwestTransport::new(http_client)),
             endpoint: endpoint.to_string(),
-            token: token.to_string(),
+            ws_endpoint: STREAMING_ENDPOINT.to_string(),
+            token: Arc::new(RwLock::new(SecretToken::new(token))),
+            retry_policy: None,
+            rate_limiter: None,
+            interceptors: vec![],
         }
     }
 
+    pub fn builder<T: Into<SecretToken>>(token: T) -> TinkoffInvestClientBuilder {
+        TinkoffInvestClientBuilder::new(token)
+    }
+
+    /// Handle sending `broker_account_id` with every account scoped call.
+    pub fn account(&self, broker_account_id: &BrokerAccountId) -> AccountClient<'_, Self> {
+        AccountClient::new(self, broker_account_id)
+    }
+
+    /// Downgrades into a client that can't place or cancel orders.
+    pub fn into_read_only(self) -> ReadOnlyClient {
+        ReadOnlyClient::from(self)
+    }
+
+    /// Restricts the client to the sandbox, see [`SandboxClient`].
+    pub fn into_sandbox(self) -> Result<SandboxClient, Error> {
+        SandboxClient::try_from(self)
+    }
+
+    /// Restricts the client to live trading, see [`ProductionClient`].
+    pub fn into_production(self) -> Result<ProductionClient, Error> {
+        ProductionClient::try_from(self)
+    }
+
+    /// Replaces the token used by all following requests and streaming connections.
+    /// Fails for a token scoped to the environment the endpoint doesn't belong to.
+    pub fn set_token(&self, token: SecretToken) -> Result<(), Error> {
+        self.ensure_token_fits(&token)?;
+        *self.token.write().unwrap() = token;
+        Ok(())
+    }
+
+    /// Refuses an endpoint or token that belongs to the other environment.
+    /// Custom endpoints are accepted for both.
+    fn ensure_scope(&self, scope: TokenScope) -> Result<(), Error> {
+        if matches!(self.endpoint_scope(), Some(endpoint_scope) if endpoint_scope != scope) {
+            return Err(Error::ConfigurationError {
+                description: format!(
+                    "Endpoint {} can't be used with a {:?} client",
+                    self.endpoint, scope
+                ),
+            });
+        }
+
+        self.token.read().unwrap().ensure_scope(scope)
+    }
+
+    /// Refuses a token scoped to the environment the endpoint doesn't belong to, so that
+    /// even an untyped client doesn't trade with a sandbox token on the live endpoint.
+    fn ensure_token_fits(&self, token: &SecretToken) -> Result<(), Error> {
+        match self.endpoint_scope() {
+            Some(scope) => token.ensure_scope(scope),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks the current token against the endpoint. Runs before every order call.
+    pub(crate) fn ensure_current_token_fits(&self) -> Result<(), Error> {
+        self.ensure_token_fits(&self.token.read().unwrap())
+    }
+
+    /// Environment of the endpoint, `None` for a custom one.
+    fn endpoint_scope(&self) -> Option<TokenScope> {
+        let endpoint = self.endpoint.trim_end_matches('/');
+
+        if endpoint == PRODUCTION_ENDPOINT {
+            Some(TokenScope::Production)
+        } else if endpoint.ends_with("/sandbox") {
+            Some(TokenScope::Sandbox)
+        } else {
+            None
+        }
+    }
+
+    pub fn rate_limiter(&self) -> Option<&RateLimiter> {
+        self.rate_limiter.as_deref()
+    }
+
+    /// Raw streaming connection. Pings and pongs are passed through as is and nothing
+    /// watches for a dead connection, [`streaming_session`](Self::streaming_session) does.
     pub async fn get_stream(
         &self,
-        ws_endpoint: &str,
     ) -> Result<
         (
             impl Sink<OutcomeEvent, Error = Error>,
@@ -52,45 +191,20 @@ impl<'a> TinkoffInvestClient {
         ),
         Error,
     > {
-        let request = http::Request::builder()
-            .method("GET")
-            .uri(ws_endpoint)
-            .header("Authorization", "Bearer ".to_owned() + &self.token)
-            .body(())?;
-        let (ws_stream, _) = connect_async(request).await?;
-
-        let (write, read) = ws_stream.split();
-
-        let write = write.sink_err_into::<Error>().with(|e: OutcomeEvent| {
-            let message = match e {
-                OutcomeEvent::Ping(b) => Ok(Message::Ping(b)),
-                OutcomeEvent::Pong(b) => Ok(Message::Pong(b)),
-                _ => serde_json::to_string(&e)
-                    .map_err(Error::from)
-                    .map(Message::text),
-            };
-            future::ready(message)
-        });
+        open_stream(self.ws_endpoint.clone(), self.bearer()).await
+    }
 
-        let read = read.then(|e| {
-            e.map_err(Error::from)
-                .and_then(|e| match e {
-                    Message::Text(text) => {
-                        serde_json::from_str::<IncomeEvent>(&text).map_err(Error::from)
-                    }
-                    Message::Binary(b) => Ok(IncomeEvent::Binary(b)),
-                    Message::Close(frame) => {
-                        dbg!(frame);
-                        Ok(IncomeEvent::Close)
-                    }
-                    Message::Ping(b) => Ok(IncomeEvent::Ping(b)),
-                    Message::Pong(b) => Ok(IncomeEvent::Pong(b)),
-                })
-                .map(future::ok)
-                .unwrap_or_else(future::err)
-        });
+    /// Streaming connection that reconnects on failures and restores subscriptions,
+    /// see [`StreamingSession`].
+    pub async fn streaming_session(
+        &self,
+        config: SessionConfig,
+    ) -> Result<StreamingSession, Error> {
+        StreamingSession::connect(self.connector(), config).await
+    }
 
-        Ok((write, read))
+    fn connector(&self) -> Connector {
+        Connector::new(self.ws_endpoint.clone(), self.token.clone())
     }
 
     async fn make_get_request<T: DeserializeOwned>(
@@ -98,28 +212,7 @@ impl<'a> TinkoffInvestClient {
         uri: &str,
         query: &HashMap<&str, &str>,
     ) -> Result<Response<T>, Error> {
-        let endpoint = self.endpoint.clone() + uri;
-
-        let result = self
-            .http_client
-            .get(&endpoint)
-            .header("Content-Type", "application/json")
-            .header("Authorization", "Bearer ".to_owned() + &self.token)
-            .query(&query)
-            .send()
-            .await?;
-
-        let status = result.status();
-        let text = result.text().await?;
-
-        if status == StatusCode::OK {
-            let resp: Response<T> = serde_json::from_str(&text)?;
-            Ok(resp)
-        } else {
-            Err(Error::GeneralError {
-                description: format!("Got unexpected response, status={} text={}", status, text),
-            })
-        }
+        self.make_request(Method::GET, uri, query, None).await
     }
 
     async fn make_post_request<T: DeserializeOwned>(
@@ -128,28 +221,395 @@ impl<'a> TinkoffInvestClient {
         query: &HashMap<&str, &str>,
         body: &str,
     ) -> Result<Response<T>, Error> {
-        let endpoint = self.endpoint.clone() + uri;
-
-        let result = self
-            .http_client
-            .post(&endpoint)
-            .header("Content-Type", "application/json")
-            .header("Authorization", "Bearer ".to_owned() + &self.token)
-            .query(&query)
-            .body(body.to_string())
-            .send()
-            .await?;
-
-        let status = result.status();
-        let text = result.text().await?;
-
-        if status == StatusCode::OK {
-            let resp: Response<T> = serde_json::from_str(&text)?;
-            Ok(resp)
-        } else {
-            Err(Error::GeneralError {
-                description: format!("Got unexpected response, status={} text={}", status, text),
-            })
+        self.make_request(Method::POST, uri, query, Some(body))
+            .await
+    }
+
+    async fn make_request<T: DeserializeOwned>(
+        &self,
+        method: Method,
+        uri: &str,
+        query: &HashMap<&str, &str>,
+        body: Option<&str>,
+    ) -> Result<Response<T>, Error> {
+        let request = self.make_attempts(&method, uri, query, body);
+
+        #[cfg(feature = "tracing")]
+        let request = tracing::Instrument::instrument(
+            request,
+            tracing::info_span!(
+                "request",
+                method = %method,
+                endpoint = uri,
+                figi = query.get("figi").copied(),
+                account = query.get("brokerAccountId").copied(),
+                tracking_id = tracing::field::Empty,
+                outcome = tracing::field::Empty,
+            ),
+        );
+
+        request.await
+    }
+
+    async fn make_attempts<T: DeserializeOwned>(
+        &self,
+        method: &Method,
+        uri: &str,
+        query: &HashMap<&str, &str>,
+        body: Option<&str>,
+    ) -> Result<Response<T>, Error> {
+        let idempotent = method == Method::GET;
+        let mut attempt = 1;
+
+        loop {
+            #[cfg(feature = "metrics")]
+            let started = Instant::now();
+
+            let (result, retry_after) = match self.send_request(method, uri, query, body).await {
+                Ok((status, retry_after, text)) => {
+                    (Self::parse_response(status, &text), retry_after)
+                }
+                Err(e) => (Err(e), None),
+            };
+
+            #[cfg(feature = "metrics")]
+            telemetry::record_request(method, uri, started.elapsed(), &result);
+
+            match (result, &self.retry_policy) {
+                (Err(e), Some(policy))
+                    if e.is_retryable() && policy.allows(idempotent, attempt) =>
+                {
+                    let delay = policy.delay(attempt, retry_after);
+                    #[cfg(feature = "tracing")]
+                    tracing::warn!(attempt, ?delay, error = %e, "Retrying request");
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                (result, _) => {
+                    #[cfg(feature = "tracing")]
+                    trace_outcome(&result);
+                    return result;
+                }
+            }
+        }
+    }
+
+    async fn send_request(
+        &self,
+        method: &Method,
+        uri: &str,
+        query: &HashMap<&str, &str>,
+        body: Option<&str>,
+    ) -> Result<(u16, Option<Duration>, String), Error> {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(EndpointGroup::from_uri(uri)).await;
         }
+
+        let mut request = HttpRequest {
+            method: method.clone(),
+            url: self.endpoint.clone() + uri,
+            headers: vec![
+                ("Content-Type".to_string(), "application/json".to_string()),
+                ("Authorization".to_string(), REDACTED_BEARER.to_string()),
+            ],
+            query: query
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            body: body.map(str::to_string),
+        };
+
+        let started = Instant::now();
+        let response = match self.intercept_request(uri, &mut request) {
+            Ok(()) => self.transport.send(self.authorize(&request)).await,
+            Err(e) => Err(e),
+        };
+
+        let info = ResponseInfo {
+            status: response.as_ref().ok().map(|r| r.status),
+            latency: started.elapsed(),
+            tracking_id: response
+                .as_ref()
+                .ok()
+                .and_then(|r| middleware::tracking_id(&r.body)),
+        };
+        #[cfg(feature = "tracing")]
+        if let Some(tracking_id) = &info.tracking_id {
+            tracing::Span::current().record("tracking_id", tracking_id.as_str());
+        }
+
+        for interceptor in &self.interceptors {
+            interceptor.on_response(uri, &request, &info);
+        }
+
+        let response = response?;
+
+        let retry_after = response
+            .header(RETRY_AFTER.as_str())
+            .and_then(parse_retry_after);
+
+        Ok((response.status, retry_after, response.body))
+    }
+
+    fn bearer(&self) -> String {
+        self.token.read().unwrap().bearer()
+    }
+
+    /// Copy of `request` carrying the token, which interceptors only see redacted.
+    fn authorize(&self, request: &HttpRequest) -> HttpRequest {
+        let mut request = request.clone();
+        request
+            .headers
+            .retain(|(name, _)| !name.eq_ignore_ascii_case(AUTHORIZATION.as_str()));
+        request
+            .headers
+            .push(("Authorization".to_string(), self.bearer()));
+        request
+    }
+
+    fn intercept_request(&self, uri: &str, request: &mut HttpRequest) -> Result<(), Error> {
+        for interceptor in &self.interceptors {
+            interceptor.on_request(uri, request)?;
+        }
+        Ok(())
+    }
+
+    /// Decodes the envelope first and the payload after looking at `status`,
+    /// so an error envelope becomes a [`Error::ServiceError`] even on HTTP 200.
+    fn parse_response<T: DeserializeOwned>(status: u16, text: &str) -> Result<Response<T>, Error> {
+        let envelope = match serde_json::from_str::<Response<serde_json::Value>>(text) {
+            Ok(envelope) => envelope,
+            Err(e) if status == 200 => return Err(e.into()),
+            Err(_) => {
+                return Err(Error::UnexpectedResponseError {
+                    http_code: status,
+                    text: text.to_string(),
+                })
+            }
+        };
+
+        if status == 200 && envelope.status == Status::Ok {
+            return Ok(Response {
+                tracking_id: envelope.tracking_id,
+                status: envelope.status,
+                payload: serde_json::from_value(envelope.payload)?,
+            });
+        }
+
+        match serde_json::from_value::<ErrorPayload>(envelope.payload) {
+            Ok(payload) => Err(Error::ServiceError {
+                http_code: status,
+                tracking_id: envelope.tracking_id,
+                code: payload.code.unwrap_or_default(),
+                message: payload.message.unwrap_or_default(),
+            }),
+            Err(_) => Err(Error::UnexpectedResponseError {
+                http_code: status,
+                text: text.to_string(),
+            }),
+        }
+    }
+}
+
+/// Opens a streaming connection. Takes owned arguments, so the connection doesn't borrow the client.
+async fn open_stream(
+    ws_endpoint: String,
+    bearer: String,
+) -> Result<
+    (
+        impl Sink<OutcomeEvent, Error = Error>,
+        impl Stream<Item = Result<IncomeEvent, Error>>,
+    ),
+    Error,
+> {
+    let request = http::Request::builder()
+        .method("GET")
+        .uri(&ws_endpoint)
+        .header("Authorization", bearer)
+        .body(())?;
+    let (ws_stream, _) = connect_async(request).await?;
+
+    let (write, read) = ws_stream.split();
+
+    #[cfg(feature = "tracing")]
+    tracing::info!(endpoint = %ws_endpoint, "Streaming connection established");
+
+    let write = write.sink_err_into::<Error>().with(|e: OutcomeEvent| {
+        #[cfg(feature = "tracing")]
+        trace_outcome_event(&e);
+
+        let message = match e {
+            OutcomeEvent::Ping(b) => Ok(Message::Ping(b)),
+            OutcomeEvent::Pong(b) => Ok(Message::Pong(b)),
+            _ => serde_json::to_string(&e)
+                .map_err(Error::from)
+                .map(Message::text),
+        };
+        future::ready(message)
+    });
+
+    let read = read.then(|e| {
+        let event = e.map_err(Error::from).and_then(|e| match e {
+            Message::Text(text) => serde_json::from_str::<IncomeEvent>(&text).map_err(Error::from),
+            Message::Binary(b) => Ok(IncomeEvent::Binary(b)),
+            Message::Close(_frame) => {
+                #[cfg(feature = "tracing")]
+                tracing::info!(frame = ?_frame, "Streaming connection closed");
+                Ok(IncomeEvent::Close)
+            }
+            Message::Ping(b) => Ok(IncomeEvent::Ping(b)),
+            Message::Pong(b) => Ok(IncomeEvent::Pong(b)),
+        });
+
+        #[cfg(feature = "metrics")]
+        if let Ok(event) = &event {
+            telemetry::record_stream_message(event);
+        }
+
+        future::ready(event)
+    });
+
+    Ok((write, read))
+}
+
+#[cfg(feature = "tracing")]
+fn trace_outcome<T>(result: &Result<Response<T>, Error>) {
+    let span = tracing::Span::current();
+
+    match result {
+        Ok(_) => {
+            span.record("outcome", "ok");
+        }
+        Err(e) => {
+            span.record("outcome", tracing::field::debug(e.kind()));
+            tracing::warn!(error = %e, "Request failed");
+        }
+    }
+}
+
+#[cfg(feature = "tracing")]
+fn trace_outcome_event(event: &OutcomeEvent) {
+    match event {
+        OutcomeEvent::Ping(_) | OutcomeEvent::Pong(_) => {}
+        _ => tracing::info!(?event, "Sending streaming subscription"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::orders::Orders;
+    use crate::user::User;
+    use crate::{Error, ErrorKind, TinkoffInvestClient};
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn service_error() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_header("Authorization", "Bearer token123")
+            .match_query(Matcher::Missing)
+            .with_status(500)
+            .with_body(
+                "{
+                        \"trackingId\": \"tracking_id_0\",
+                        \"status\": \"Error\",
+                        \"payload\": {
+                            \"message\": \"Internal error\",
+                            \"code\": \"INTERNAL_ERROR\"
+                        }
+                }",
+            )
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let token = "token123";
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
+
+        match tinkoff.accounts().await {
+            Err(Error::ServiceError {
+                http_code,
+                tracking_id,
+                code,
+                message,
+            }) => {
+                assert_eq!(http_code, 500);
+                assert_eq!(tracking_id, "tracking_id_0");
+                assert_eq!(code, "INTERNAL_ERROR");
+                assert_eq!(message, "Internal error");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn service_error_with_ok_status() {
+        let mock = mockito::mock("POST", "/orders/market-order")
+            .match_query(Matcher::Any)
+            .with_body(
+                "{
+                        \"trackingId\": \"tracking_id_0\",
+                        \"status\": \"Error\",
+                        \"payload\": {
+                            \"message\": \"Not enough balance\",
+                            \"code\": \"NOT_ENOUGH_BALANCE\"
+                        }
+                }",
+            )
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let token = "token123";
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
+
+        let error = tinkoff
+            .make_market_order(&Figi::new("BBG000B9XRY4").unwrap(), None, Operation::Buy, 1)
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InsufficientFunds);
+        match error {
+            Error::ServiceError {
+                http_code,
+                tracking_id,
+                code,
+                message,
+            } => {
+                assert_eq!(http_code, 200);
+                assert_eq!(tracking_id, "tracking_id_0");
+                assert_eq!(code, "NOT_ENOUGH_BALANCE");
+                assert_eq!(message, "Not enough balance");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn service_error_without_envelope() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_header("Authorization", "Bearer token123")
+            .match_query(Matcher::Missing)
+            .with_status(404)
+            .with_body("Not Found")
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let token = "token123";
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
+
+        let error = tinkoff.accounts().await.unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Validation);
+        match error {
+            Error::UnexpectedResponseError { http_code, text } => {
+                assert_eq!(http_code, 404);
+                assert_eq!(text, "Not Found");
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+
+        mock.assert();
     }
 }
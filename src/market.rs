@@ -2,7 +2,7 @@ use crate::domain::*;
 use crate::errors::Error;
 use crate::TinkoffInvestClient;
 use async_trait::async_trait;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, FixedOffset, TimeZone};
 use std::collections::HashMap;
 
 #[async_trait]
@@ -15,28 +15,52 @@ pub trait Market {
 
     async fn currencies(&self) -> Result<Response<MarketInstrumentListPayload>, Error>;
 
-    async fn order_book(&self, figi: &str, depth: i32)
-        -> Result<Response<OrderBookPayload>, Error>;
+    async fn order_book(
+        &self,
+        figi: &Figi,
+        depth: i32,
+    ) -> Result<Response<OrderBookPayload>, Error>;
 
     async fn candles(
         &self,
-        figi: &str,
-        from: &DateTime<Local>,
-        to: &DateTime<Local>,
+        figi: &Figi,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
         interval: &Interval,
     ) -> Result<Response<CandlesPayload>, Error>;
 
     async fn search_by_figi(
         &self,
-        figi: &str,
+        figi: &Figi,
     ) -> Result<Response<SearchMarketInstrumentPayload>, Error>;
 
     async fn search_by_ticker(
         &self,
-        ticker: &str,
+        ticker: &Ticker,
     ) -> Result<Response<MarketInstrumentListPayload>, Error>;
 }
 
+/// [`Market`] requests taking times in any time zone, e.g. `Utc` or `Local`.
+#[async_trait]
+pub trait MarketExt: Market + Sync {
+    async fn candles_tz<Tz>(
+        &self,
+        figi: &Figi,
+        from: &DateTime<Tz>,
+        to: &DateTime<Tz>,
+        interval: &Interval,
+    ) -> Result<Response<CandlesPayload>, Error>
+    where
+        Tz: TimeZone,
+        Tz::Offset: Sync,
+    {
+        self.candles(figi, &from.fixed_offset(), &to.fixed_offset(), interval)
+            .await
+    }
+}
+
+impl<T: Market + Sync + ?Sized> MarketExt for T {}
+
 #[async_trait]
 impl Market for TinkoffInvestClient {
     async fn stocks(&self) -> Result<Response<MarketInstrumentListPayload>, Error> {
@@ -61,13 +85,13 @@ impl Market for TinkoffInvestClient {
 
     async fn order_book(
         &self,
-        figi: &str,
+        figi: &Figi,
         depth: i32,
     ) -> Result<Response<OrderBookPayload>, Error> {
         let depth = format!("{}", depth);
 
         let mut query = HashMap::new();
-        query.insert("figi", figi);
+        query.insert("figi", figi.as_str());
         query.insert("depth", &depth);
 
         self.make_get_request("/market/orderbook", &query).await
@@ -75,16 +99,16 @@ impl Market for TinkoffInvestClient {
 
     async fn candles(
         &self,
-        figi: &str,
-        from: &DateTime<Local>,
-        to: &DateTime<Local>,
+        figi: &Figi,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
         interval: &Interval,
     ) -> Result<Response<CandlesPayload>, Error> {
         let mut query = HashMap::new();
         let from = from.to_rfc3339();
         let to = to.to_rfc3339();
         let interval = interval.to_string();
-        query.insert("figi", figi);
+        query.insert("figi", figi.as_str());
         query.insert("from", &from);
         query.insert("to", &to);
         query.insert("interval", &interval);
@@ -94,10 +118,10 @@ impl Market for TinkoffInvestClient {
 
     async fn search_by_figi(
         &self,
-        figi: &str,
+        figi: &Figi,
     ) -> Result<Response<SearchMarketInstrumentPayload>, Error> {
         let mut query = HashMap::new();
-        query.insert("figi", figi);
+        query.insert("figi", figi.as_str());
 
         self.make_get_request("/market/search/by-figi", &query)
             .await
@@ -105,10 +129,10 @@ impl Market for TinkoffInvestClient {
 
     async fn search_by_ticker(
         &self,
-        ticker: &str,
+        ticker: &Ticker,
     ) -> Result<Response<MarketInstrumentListPayload>, Error> {
         let mut query = HashMap::new();
-        query.insert("ticker", ticker);
+        query.insert("ticker", ticker.as_str());
 
         self.make_get_request("/market/search/by-ticker", &query)
             .await
@@ -119,10 +143,10 @@ impl Market for TinkoffInvestClient {
 mod tests {
 
     use crate::domain::Interval;
-    use crate::market::Market;
+    use crate::domain::*;
+    use crate::market::{Market, MarketExt};
     use crate::TinkoffInvestClient;
-    use chrono::{Local, TimeZone};
-    use mockito;
+    use chrono::{DateTime, TimeZone, Utc};
     use mockito::Matcher;
 
     #[tokio::test]
@@ -141,7 +165,7 @@ mod tests {
                             \"total\": 1,
                             \"instruments\": [
                                 {
-                                    \"figi\": \"figi_0\",
+                                    \"figi\": \"BBG000B9XRY4\",
                                     \"ticker\": \"ticker_0\",
                                     \"isin\": \"isin_0\",
                                     \"minPriceIncrement\": 1.23,
@@ -151,7 +175,7 @@ mod tests {
                                     \"type\": \"Stock\"
                                 },
                                 {
-                                    \"figi\": \"figi_1\",
+                                    \"figi\": \"BBG005DXJS36\",
                                     \"ticker\": \"ticker_1\",
                                     \"lot\": 123,
                                     \"name\": \"name_0\",
@@ -165,7 +189,7 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff.stocks().await.unwrap();
 
@@ -188,7 +212,7 @@ mod tests {
                             \"total\": 1,
                             \"instruments\": [
                                 {
-                                    \"figi\": \"figi_1\",
+                                    \"figi\": \"BBG005DXJS36\",
                                     \"ticker\": \"ticker_1\",
                                     \"lot\": 123,
                                     \"name\": \"name_0\",
@@ -202,7 +226,7 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff.bonds().await.unwrap();
 
@@ -225,7 +249,7 @@ mod tests {
                             \"total\": 1,
                             \"instruments\": [
                                 {
-                                    \"figi\": \"figi_1\",
+                                    \"figi\": \"BBG005DXJS36\",
                                     \"ticker\": \"ticker_1\",
                                     \"lot\": 123,
                                     \"name\": \"name_0\",
@@ -239,7 +263,7 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff.etfs().await.unwrap();
 
@@ -262,7 +286,7 @@ mod tests {
                             \"total\": 1,
                             \"instruments\": [
                                 {
-                                    \"figi\": \"figi_1\",
+                                    \"figi\": \"BBG005DXJS36\",
                                     \"ticker\": \"ticker_1\",
                                     \"lot\": 123,
                                     \"name\": \"name_0\",
@@ -276,7 +300,7 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff.currencies().await.unwrap();
 
@@ -291,7 +315,7 @@ mod tests {
             .match_header("accept", Matcher::Any)
             .match_header("host", Matcher::Any)
             .match_query(Matcher::AllOf(vec![
-                Matcher::UrlEncoded("figi".to_string(), "figi_0".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
                 Matcher::UrlEncoded("depth".to_string(), "1".to_string()),
             ]))
             .with_body(
@@ -299,7 +323,7 @@ mod tests {
                     \"trackingId\": \"tracking_id_0\",
                     \"status\": \"Ok\",
                     \"payload\": {
-                        \"figi\": \"figi_0\",
+                        \"figi\": \"BBG000B9XRY4\",
                         \"depth\": 1,
                         \"bids\": [
                             {
@@ -327,9 +351,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.order_book("figi_0", 1).await.unwrap();
+        tinkoff
+            .order_book(&Figi::new("BBG000B9XRY4").unwrap(), 1)
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -342,7 +369,7 @@ mod tests {
             .match_header("accept", Matcher::Any)
             .match_header("host", Matcher::Any)
             .match_query(Matcher::AllOf(vec![
-                Matcher::UrlEncoded("figi".to_string(), "figi_0".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
                 Matcher::UrlEncoded("depth".to_string(), "1".to_string()),
             ]))
             .with_body(
@@ -350,7 +377,7 @@ mod tests {
                     \"trackingId\": \"tracking_id_0\",
                     \"status\": \"Ok\",
                     \"payload\": {
-                        \"figi\": \"figi_0\",
+                        \"figi\": \"BBG000B9XRY4\",
                         \"depth\": 1,
                         \"bids\": [
                             {
@@ -373,9 +400,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.order_book("figi_0", 1).await.unwrap();
+        tinkoff
+            .order_book(&Figi::new("BBG000B9XRY4").unwrap(), 1)
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -388,7 +418,7 @@ mod tests {
             .match_header("accept", Matcher::Any)
             .match_header("host", Matcher::Any)
             .match_query(Matcher::AllOf(vec![
-                Matcher::UrlEncoded("figi".to_string(), "figi_0".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
                 Matcher::UrlEncoded("interval".to_string(), "1min".to_string()),
                 Matcher::UrlEncoded("from".to_string(), "2020-01-01T00:00:00+03:00".to_string()),
                 Matcher::UrlEncoded("to".to_string(), "2020-01-01T00:00:00+03:00".to_string()),
@@ -398,11 +428,11 @@ mod tests {
                     \"trackingId\": \"tracking_id_0\",
                     \"status\": \"Ok\",
                     \"payload\": {
-                        \"figi\": \"figi_0\",
+                        \"figi\": \"BBG000B9XRY4\",
                         \"interval\": \"1min\",
                         \"candles\": [
                             {
-                                \"figi\": \"figi_0\",
+                                \"figi\": \"BBG000B9XRY4\",
                                 \"interval\": \"1min\",
                                 \"o\": 1.23,
                                 \"c\": 2.34,
@@ -419,16 +449,100 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        let from = Local
-            .datetime_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z")
+        let from =
+            DateTime::parse_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z").unwrap();
+        let to =
+            DateTime::parse_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z").unwrap();
+        tinkoff
+            .candles(
+                &Figi::new("BBG000B9XRY4").unwrap(),
+                &from,
+                &to,
+                &Interval::_1min,
+            )
+            .await
             .unwrap();
-        let to = Local
-            .datetime_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z")
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn candles_interval() {
+        let mock = mockito::mock("GET", "/market/candles")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("figi".to_string(), "BBG005DXJS36".to_string()),
+                Matcher::UrlEncoded("interval".to_string(), "hour".to_string()),
+            ]))
+            .with_body(
+                "{
+                    \"trackingId\": \"tracking_id_0\",
+                    \"status\": \"Ok\",
+                    \"payload\": {
+                        \"figi\": \"BBG005DXJS36\",
+                        \"interval\": \"hour\",
+                        \"candles\": []
+                    }
+                }",
+            )
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let token = "token123";
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
+
+        let from =
+            DateTime::parse_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z").unwrap();
+        let to =
+            DateTime::parse_from_str("2020-01-02T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z").unwrap();
+        let candles = tinkoff
+            .candles(
+                &Figi::new("BBG005DXJS36").unwrap(),
+                &from,
+                &to,
+                &Interval::Hour,
+            )
+            .await
             .unwrap();
-        tinkoff
-            .candles("figi_0", &from, &to, &Interval::_1min)
+
+        assert_eq!(candles.payload.interval, Interval::Hour);
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn candles_tz() {
+        let mock = mockito::mock("GET", "/market/candles")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("figi".to_string(), "BBG004730N88".to_string()),
+                Matcher::UrlEncoded("from".to_string(), "2020-01-01T07:00:00+00:00".to_string()),
+                Matcher::UrlEncoded("to".to_string(), "2020-01-01T15:00:00+00:00".to_string()),
+            ]))
+            .with_body(
+                "{
+                    \"trackingId\": \"tracking_id_0\",
+                    \"status\": \"Ok\",
+                    \"payload\": {
+                        \"figi\": \"BBG004730N88\",
+                        \"interval\": \"hour\",
+                        \"candles\": []
+                    }
+                }",
+            )
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let token = "token123";
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
+        let market: &(dyn Market + Sync) = &tinkoff;
+
+        market
+            .candles_tz(
+                &Figi::new("BBG004730N88").unwrap(),
+                &Utc.with_ymd_and_hms(2020, 1, 1, 7, 0, 0).unwrap(),
+                &Utc.with_ymd_and_hms(2020, 1, 1, 15, 0, 0).unwrap(),
+                &Interval::Hour,
+            )
             .await
             .unwrap();
 
@@ -444,14 +558,14 @@ mod tests {
             .match_header("host", Matcher::Any)
             .match_query(Matcher::UrlEncoded(
                 "figi".to_string(),
-                "figi_0".to_string(),
+                "BBG000B9XRY4".to_string(),
             ))
             .with_body(
                 "{
                     \"trackingId\": \"tracking_id_0\",
                     \"status\": \"Ok\",
                     \"payload\": {
-                        \"figi\": \"figi_0\",
+                        \"figi\": \"BBG000B9XRY4\",
                         \"ticker\": \"ticker_0\",
                         \"isin\": \"isin_0\",
                         \"minPriceIncrement\": 1.23,
@@ -466,9 +580,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.search_by_figi("figi_0").await.unwrap();
+        tinkoff
+            .search_by_figi(&Figi::new("BBG000B9XRY4").unwrap())
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -482,14 +599,14 @@ mod tests {
             .match_header("host", Matcher::Any)
             .match_query(Matcher::UrlEncoded(
                 "figi".to_string(),
-                "figi_0".to_string(),
+                "BBG000B9XRY4".to_string(),
             ))
             .with_body(
                 "{
                     \"trackingId\": \"tracking_id_0\",
                     \"status\": \"Ok\",
                     \"payload\": {
-                        \"figi\": \"figi_0\",
+                        \"figi\": \"BBG000B9XRY4\",
                         \"ticker\": \"ticker_0\",
                         \"lot\": 10,
                         \"name\": \"name_0\",
@@ -501,9 +618,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.search_by_figi("figi_0").await.unwrap();
+        tinkoff
+            .search_by_figi(&Figi::new("BBG000B9XRY4").unwrap())
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -527,7 +647,7 @@ mod tests {
                         \"total\": 1,
                         \"instruments\": [
                             {
-                                \"figi\": \"figi_0\",
+                                \"figi\": \"BBG000B9XRY4\",
                                 \"ticker\": \"ticker_0\",
                                 \"isin\": \"isin_0\",
                                 \"minPriceIncrement\": 1.23,
@@ -544,9 +664,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.search_by_ticker("ticker_0").await.unwrap();
+        tinkoff
+            .search_by_ticker(&Ticker::new("ticker_0").unwrap())
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -570,7 +693,7 @@ mod tests {
                         \"total\": 1,
                         \"instruments\": [
                             {
-                                \"figi\": \"figi_0\",
+                                \"figi\": \"BBG000B9XRY4\",
                                 \"ticker\": \"ticker_0\",
                                 \"lot\": 10,
                                 \"name\": \"name_0\",
@@ -584,9 +707,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.search_by_ticker("ticker_0").await.unwrap();
+        tinkoff
+            .search_by_ticker(&Ticker::new("ticker_0").unwrap())
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -0,0 +1,157 @@
+use crate::domain::TrackingId;
+use crate::errors::Error;
+use crate::transport::HttpRequest;
+use serde_derive::Deserialize;
+use std::time::Duration;
+
+/// Outcome of a single REST attempt as seen by an [`Interceptor`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseInfo {
+    /// HTTP status, `None` when the transport failed before a reply arrived.
+    pub status: Option<u16>,
+    pub latency: Duration,
+    pub tracking_id: Option<TrackingId>,
+}
+
+/// Hook around every REST attempt made by [`TinkoffInvestClient`](crate::TinkoffInvestClient),
+/// including retries. Interceptors run in registration order.
+///
+/// Interceptors never see the token: the `Authorization` header they get reads
+/// `Bearer ***`, and the real one replaces it after all of them have run.
+pub trait Interceptor: Send + Sync {
+    /// Called before the request is sent; may add headers or rewrite it.
+    /// Returning an error fails the attempt without sending anything.
+    fn on_request(&self, _path: &str, _request: &mut HttpRequest) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn on_response(&self, _path: &str, _request: &HttpRequest, _response: &ResponseInfo) {}
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TrackedEnvelope {
+    tracking_id: Option<TrackingId>,
+}
+
+pub(crate) fn tracking_id(body: &str) -> Option<TrackingId> {
+    serde_json::from_str::<TrackedEnvelope>(body)
+        .ok()
+        .and_then(|e| e.tracking_id)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::middleware::{Interceptor, ResponseInfo};
+    use crate::transport::HttpRequest;
+    use crate::user::User;
+    use crate::{Environment, Error, TinkoffInvestClient};
+    use mockito::Matcher;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Default)]
+    struct Recorder {
+        requests: Mutex<Vec<String>>,
+        authorization: Mutex<Vec<String>>,
+        responses: Mutex<Vec<ResponseInfo>>,
+    }
+
+    impl Interceptor for Recorder {
+        fn on_request(&self, path: &str, request: &mut HttpRequest) -> Result<(), Error> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", request.method, path));
+            self.authorization
+                .lock()
+                .unwrap()
+                .extend(request.header("authorization").map(str::to_string));
+            request
+                .headers
+                .push(("x-request-source".to_string(), "tests".to_string()));
+            Ok(())
+        }
+
+        fn on_response(&self, _path: &str, _request: &HttpRequest, response: &ResponseInfo) {
+            self.responses.lock().unwrap().push(response.clone());
+        }
+    }
+
+    struct FailAll;
+
+    impl Interceptor for FailAll {
+        fn on_request(&self, path: &str, _request: &mut HttpRequest) -> Result<(), Error> {
+            Err(Error::GeneralError {
+                description: format!("Injected failure for {}", path),
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn sees_requests_and_responses() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_header("x-request-source", "tests")
+            .match_header("authorization", "Bearer token123")
+            .match_query(Matcher::Missing)
+            .with_body(
+                "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"accounts\": []}}",
+            )
+            .create();
+
+        let recorder = Arc::new(Recorder::default());
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .interceptor(recorder.clone())
+            .build()
+            .unwrap();
+
+        tinkoff.accounts().await.unwrap();
+
+        assert_eq!(
+            *recorder.requests.lock().unwrap(),
+            vec!["GET /user/accounts".to_string()]
+        );
+        assert_eq!(
+            *recorder.authorization.lock().unwrap(),
+            vec!["Bearer ***".to_string()]
+        );
+        let responses = recorder.responses.lock().unwrap();
+        assert_eq!(responses.len(), 1);
+        assert_eq!(responses[0].status, Some(200));
+        assert_eq!(responses[0].tracking_id.as_ref().unwrap(), "tracking_id_0");
+
+        mock.assert();
+    }
+
+    #[tokio::test]
+    async fn injects_faults() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_query(Matcher::Missing)
+            .expect(0)
+            .create();
+
+        let recorder = Arc::new(Recorder::default());
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .interceptor(Arc::new(FailAll))
+            .interceptor(recorder.clone())
+            .build()
+            .unwrap();
+
+        let error = tinkoff.accounts().await.unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Injected failure for /user/accounts"));
+        assert!(recorder.requests.lock().unwrap().is_empty());
+        assert_eq!(recorder.responses.lock().unwrap()[0].status, None);
+
+        mock.assert();
+    }
+}
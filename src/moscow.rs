@@ -0,0 +1,58 @@
+//! Moscow time, which the exchange and the broker run on.
+//!
+//! Moscow has stayed at UTC+3 without daylight saving since 2014,
+//! so a fixed offset is exact for any current date.
+
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+
+const OFFSET_SECONDS: i32 = 3 * 3600;
+
+pub fn offset() -> FixedOffset {
+    FixedOffset::east_opt(OFFSET_SECONDS).unwrap()
+}
+
+pub fn now() -> DateTime<FixedOffset> {
+    to_moscow(&Utc::now())
+}
+
+pub fn to_moscow<Tz: TimeZone>(time: &DateTime<Tz>) -> DateTime<FixedOffset> {
+    time.with_timezone(&offset())
+}
+
+/// Moscow wall clock `time` on `date`, e.g. the start of a trading session.
+pub fn at(date: NaiveDate, time: NaiveTime) -> DateTime<FixedOffset> {
+    offset()
+        .from_local_datetime(&date.and_time(time))
+        .single()
+        .unwrap()
+}
+
+/// Start of `date` and start of the next day in Moscow, for requesting a whole trading day.
+pub fn day_bounds(date: NaiveDate) -> (DateTime<FixedOffset>, DateTime<FixedOffset>) {
+    let start = at(date, NaiveTime::MIN);
+
+    (start, start + Duration::days(1))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::moscow;
+    use chrono::{NaiveDate, NaiveTime, TimeZone, Utc};
+
+    #[test]
+    fn day_bounds() {
+        let date = NaiveDate::from_ymd_opt(2021, 11, 1).unwrap();
+        let (start, end) = moscow::day_bounds(date);
+
+        assert_eq!(start.to_rfc3339(), "2021-11-01T00:00:00+03:00");
+        assert_eq!(end.to_rfc3339(), "2021-11-02T00:00:00+03:00");
+        assert_eq!(start, Utc.with_ymd_and_hms(2021, 10, 31, 21, 0, 0).unwrap());
+
+        let open = moscow::at(date, NaiveTime::from_hms_opt(10, 0, 0).unwrap());
+        assert_eq!(
+            moscow::to_moscow(&Utc.with_ymd_and_hms(2021, 11, 1, 7, 0, 0).unwrap()),
+            open
+        );
+    }
+}
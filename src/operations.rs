@@ -2,28 +2,54 @@ use crate::domain::*;
 use crate::errors::Error;
 use crate::TinkoffInvestClient;
 use async_trait::async_trait;
-use chrono::{DateTime, Local};
+use chrono::{DateTime, FixedOffset, TimeZone};
 use std::collections::HashMap;
 
 #[async_trait]
 pub trait Operations {
     async fn operations(
         &self,
-        from: &DateTime<Local>,
-        to: &DateTime<Local>,
-        figi: Option<&str>,
-        broker_account_id: Option<&str>,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        figi: Option<&Figi>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<OperationsPayload>, Error>;
 }
 
+/// [`Operations`] requests taking times in any time zone, e.g. `Utc` or `Local`.
+#[async_trait]
+pub trait OperationsExt: Operations + Sync {
+    async fn operations_tz<Tz>(
+        &self,
+        from: &DateTime<Tz>,
+        to: &DateTime<Tz>,
+        figi: Option<&Figi>,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<OperationsPayload>, Error>
+    where
+        Tz: TimeZone,
+        Tz::Offset: Sync,
+    {
+        self.operations(
+            &from.fixed_offset(),
+            &to.fixed_offset(),
+            figi,
+            broker_account_id,
+        )
+        .await
+    }
+}
+
+impl<T: Operations + Sync + ?Sized> OperationsExt for T {}
+
 #[async_trait]
 impl Operations for TinkoffInvestClient {
     async fn operations(
         &self,
-        from: &DateTime<Local>,
-        to: &DateTime<Local>,
-        figi: Option<&str>,
-        broker_account_id: Option<&str>,
+        from: &DateTime<FixedOffset>,
+        to: &DateTime<FixedOffset>,
+        figi: Option<&Figi>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<OperationsPayload>, Error> {
         let mut query = HashMap::new();
 
@@ -31,11 +57,11 @@ impl Operations for TinkoffInvestClient {
         let to = to.to_rfc3339();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
         if let Some(f) = figi {
-            query.insert("figi", f);
+            query.insert("figi", f.as_str());
         }
 
         query.insert("from", &from);
@@ -48,10 +74,10 @@ impl Operations for TinkoffInvestClient {
 #[cfg(test)]
 mod tests {
 
-    use crate::operations::Operations;
+    use crate::domain::*;
+    use crate::operations::{Operations, OperationsExt};
     use crate::TinkoffInvestClient;
-    use chrono::{Local, TimeZone};
-    use mockito;
+    use chrono::{DateTime, TimeZone, Utc};
     use mockito::Matcher;
 
     #[tokio::test]
@@ -63,7 +89,7 @@ mod tests {
             .match_header("host", Matcher::Any)
             .match_query(Matcher::AllOf(vec![
                 Matcher::UrlEncoded("brokerAccountId".to_string(), "account_123".to_string()),
-                Matcher::UrlEncoded("figi".to_string(), "figi_0".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
                 Matcher::UrlEncoded("from".to_string(), "2020-01-01T00:00:00+03:00".to_string()),
                 Matcher::UrlEncoded("to".to_string(), "2020-01-01T00:00:00+03:00".to_string()),
             ]))
@@ -92,7 +118,7 @@ mod tests {
                             \"payment\": 123.45,
                             \"price\": 123.45,
                             \"quantity\": 10,
-                            \"figi\": \"figi_0\",
+                            \"figi\": \"BBG000B9XRY4\",
                             \"instrumentType\": \"Stock\",
                             \"isMarginCall\": false,
                             \"date\": \"2020-02-01T18:38:33.131642+03:00\",
@@ -106,16 +132,54 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        let from = Local
-            .datetime_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z")
-            .unwrap();
-        let to = Local
-            .datetime_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z")
-            .unwrap();
+        let from =
+            DateTime::parse_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z").unwrap();
+        let to =
+            DateTime::parse_from_str("2020-01-01T00:00:00+03:00", "%Y-%m-%dT%H:%M:%S%:z").unwrap();
         tinkoff
-            .operations(&from, &to, Some("figi_0"), Some("account_123"))
+            .operations(
+                &from,
+                &to,
+                Some(&Figi::new("BBG000B9XRY4").unwrap()),
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+            )
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+    #[tokio::test]
+    async fn operations_tz() {
+        let mock = mockito::mock("GET", "/operations")
+            .match_query(Matcher::AllOf(vec![
+                Matcher::UrlEncoded("from".to_string(), "2020-01-01T07:00:00+00:00".to_string()),
+                Matcher::UrlEncoded("to".to_string(), "2020-01-01T15:00:00+00:00".to_string()),
+            ]))
+            .with_body(
+                "{
+                    \"trackingId\": \"tracking_id_0\",
+                    \"status\": \"Ok\",
+                    \"payload\": {
+                        \"operations\": []
+                    }
+                }",
+            )
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let token = "token123";
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
+        let operations: &(dyn Operations + Sync) = &tinkoff;
+
+        operations
+            .operations_tz(
+                &Utc.with_ymd_and_hms(2020, 1, 1, 7, 0, 0).unwrap(),
+                &Utc.with_ymd_and_hms(2020, 1, 1, 15, 0, 0).unwrap(),
+                None,
+                None,
+            )
             .await
             .unwrap();
 
@@ -8,30 +8,30 @@ use std::collections::HashMap;
 pub trait Orders {
     async fn orders(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<Vec<OrdersPayload>>, Error>;
 
     async fn make_limit_order(
         &self,
-        figi: &str,
-        broker_account_id: Option<&str>,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
         operation: Operation,
         lots: i32,
-        price: f64,
+        price: Amount,
     ) -> Result<Response<LimitOrderPayload>, Error>;
 
     async fn make_market_order(
         &self,
-        figi: &str,
-        broker_account_id: Option<&str>,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
         operation: Operation,
         lots: i32,
     ) -> Result<Response<MarketOrderPayload>, Error>;
 
     async fn cancel_order(
         &self,
-        order_id: &str,
-        broker_account_id: Option<&str>,
+        order_id: &OrderId,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<EmptyPayload>, Error>;
 }
 
@@ -39,12 +39,14 @@ pub trait Orders {
 impl Orders for TinkoffInvestClient {
     async fn orders(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<Vec<OrdersPayload>>, Error> {
+        self.ensure_current_token_fits()?;
+
         let mut query = HashMap::new();
 
         broker_account_id.iter().for_each(|a| {
-            query.insert("brokerAccountId", *a);
+            query.insert("brokerAccountId", a.as_str());
         });
 
         self.make_get_request("/orders", &query).await
@@ -52,12 +54,14 @@ impl Orders for TinkoffInvestClient {
 
     async fn make_limit_order(
         &self,
-        figi: &str,
-        broker_account_id: Option<&str>,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
         operation: Operation,
         lots: i32,
-        price: f64,
+        price: Amount,
     ) -> Result<Response<LimitOrderPayload>, Error> {
+        self.ensure_current_token_fits()?;
+
         let request = OrderRequest::MakeLimitOrder {
             operation,
             lots,
@@ -67,10 +71,10 @@ impl Orders for TinkoffInvestClient {
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
-        query.insert("figi", &figi);
+        query.insert("figi", figi.as_str());
 
         let body = serde_json::to_string(&request)?;
 
@@ -80,20 +84,22 @@ impl Orders for TinkoffInvestClient {
 
     async fn make_market_order(
         &self,
-        figi: &str,
-        broker_account_id: Option<&str>,
+        figi: &Figi,
+        broker_account_id: Option<&BrokerAccountId>,
         operation: Operation,
         lots: i32,
     ) -> Result<Response<MarketOrderPayload>, Error> {
+        self.ensure_current_token_fits()?;
+
         let request = OrderRequest::MakeMarketOrder { operation, lots };
 
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
-        query.insert("figi", &figi);
+        query.insert("figi", figi.as_str());
 
         let body = serde_json::to_string(&request)?;
 
@@ -103,16 +109,18 @@ impl Orders for TinkoffInvestClient {
 
     async fn cancel_order(
         &self,
-        order_id: &str,
-        broker_account_id: Option<&str>,
+        order_id: &OrderId,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<EmptyPayload>, Error> {
+        self.ensure_current_token_fits()?;
+
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
-        query.insert("orderId", &order_id);
+        query.insert("orderId", order_id.as_str());
 
         self.make_post_request("/orders/cancel", &query, "").await
     }
@@ -124,7 +132,6 @@ mod tests {
     use crate::domain::*;
     use crate::orders::Orders;
     use crate::TinkoffInvestClient;
-    use mockito;
     use mockito::Matcher;
 
     #[tokio::test]
@@ -145,7 +152,7 @@ mod tests {
                         \"payload\":[
                             {
                                 \"orderId\":\"order_0\",
-                                \"figi\":\"figi_0\",
+                                \"figi\":\"BBG000B9XRY4\",
                                 \"operation\":\"Buy\",
                                 \"status\":\"New\",
                                 \"requestedLots\":10,
@@ -155,7 +162,7 @@ mod tests {
                             },
                             {
                                 \"orderId\":\"order_1\",
-                                \"figi\":\"figi_1\",
+                                \"figi\":\"BBG005DXJS36\",
                                 \"operation\":\"Sell\",
                                 \"status\":\"Cancelled\",
                                 \"requestedLots\":3,
@@ -169,9 +176,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.orders(Some("account_123")).await.unwrap();
+        tinkoff
+            .orders(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -186,7 +196,7 @@ mod tests {
             .match_header("content-length", Matcher::Any)
             .match_query(Matcher::AllOf(vec![
                 Matcher::UrlEncoded("brokerAccountId".to_string(), "account_123".to_string()),
-                Matcher::UrlEncoded("figi".to_string(), "figi_0".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
             ]))
             .match_body(Matcher::JsonString(
                 "{\"operation\":\"Buy\",\"lots\":10,\"price\":12.34}".to_string(),
@@ -214,10 +224,16 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff
-            .make_limit_order("figi_0", Some("account_123"), Operation::Buy, 10, 12.34)
+            .make_limit_order(
+                &Figi::new("BBG000B9XRY4").unwrap(),
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+                Operation::Buy,
+                10,
+                "12.34".parse().unwrap(),
+            )
             .await
             .unwrap();
 
@@ -234,7 +250,7 @@ mod tests {
             .match_header("content-length", Matcher::Any)
             .match_query(Matcher::AllOf(vec![
                 Matcher::UrlEncoded("brokerAccountId".to_string(), "account_123".to_string()),
-                Matcher::UrlEncoded("figi".to_string(), "figi_0".to_string()),
+                Matcher::UrlEncoded("figi".to_string(), "BBG000B9XRY4".to_string()),
             ]))
             .match_body(Matcher::JsonString(
                 "{\"operation\":\"Buy\",\"lots\":10}".to_string(),
@@ -262,10 +278,15 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff
-            .make_market_order("figi_0", Some("account_123"), Operation::Buy, 10)
+            .make_market_order(
+                &Figi::new("BBG000B9XRY4").unwrap(),
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+                Operation::Buy,
+                10,
+            )
             .await
             .unwrap();
 
@@ -295,10 +316,13 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff
-            .cancel_order("order_0", Some("account_123"))
+            .cancel_order(
+                &OrderId::new("order_0").unwrap(),
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+            )
             .await
             .unwrap();
 
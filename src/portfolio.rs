@@ -8,12 +8,12 @@ use std::collections::HashMap;
 pub trait Portfolio {
     async fn portfolio(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<PortfolioPayload>, Error>;
 
     async fn portfolio_currencies(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<PortfolioCurrenciesPayload>, Error>;
 }
 
@@ -21,12 +21,12 @@ pub trait Portfolio {
 impl Portfolio for TinkoffInvestClient {
     async fn portfolio(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<PortfolioPayload>, Error> {
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
         self.make_get_request("/portfolio", &query).await
@@ -34,12 +34,12 @@ impl Portfolio for TinkoffInvestClient {
 
     async fn portfolio_currencies(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<PortfolioCurrenciesPayload>, Error> {
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
         self.make_get_request("/portfolio/currencies", &query).await
@@ -49,9 +49,9 @@ impl Portfolio for TinkoffInvestClient {
 #[cfg(test)]
 mod tests {
 
+    use crate::domain::*;
     use crate::portfolio::Portfolio;
     use crate::TinkoffInvestClient;
-    use mockito;
     use mockito::Matcher;
 
     #[tokio::test]
@@ -72,7 +72,7 @@ mod tests {
                         \"payload\": {
                             \"positions\": [
                                 {
-                                    \"figi\": \"figi_0\",
+                                    \"figi\": \"BBG000B9XRY4\",
                                     \"ticker\": \"ticker_0\",
                                     \"isin\": \"isin_0\",
                                     \"instrumentType\": \"Stock\",
@@ -94,7 +94,7 @@ mod tests {
                                     \"name\": \"name_0\"
                                 },
                                 {
-                                    \"figi\": \"figi_1\",
+                                    \"figi\": \"BBG005DXJS36\",
                                     \"instrumentType\": \"Etf\",
                                     \"balance\": 12.34,
                                     \"lots\": 10,
@@ -108,9 +108,12 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
-        tinkoff.portfolio(Some("account_123")).await.unwrap();
+        tinkoff
+            .portfolio(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -149,10 +152,10 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff
-            .portfolio_currencies(Some("account_123"))
+            .portfolio_currencies(Some(&BrokerAccountId::new("account_123").unwrap()))
             .await
             .unwrap();
 
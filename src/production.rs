@@ -0,0 +1,125 @@
+use crate::errors::Error;
+use crate::read_only::ReadOnlyClient;
+use crate::token::{SecretToken, TokenScope};
+use crate::TinkoffInvestClient;
+
+/// Client bound to live trading. Unlike [`SandboxClient`](crate::SandboxClient)
+/// it doesn't implement [`Sandbox`](crate::Sandbox).
+pub struct ProductionClient {
+    client: TinkoffInvestClient,
+}
+
+impl ProductionClient {
+    /// Replaces the token used by all following requests and streaming connections.
+    /// Fails for a token scoped to the sandbox.
+    pub fn set_token(&self, token: SecretToken) -> Result<(), Error> {
+        token.ensure_scope(TokenScope::Production)?;
+        self.client.set_token(token)
+    }
+
+    /// Downgrades into a client that can't place or cancel orders.
+    pub fn into_read_only(self) -> ReadOnlyClient {
+        ReadOnlyClient::from(self.client)
+    }
+}
+
+/// Fails when the client points to the sandbox endpoint or has a sandbox token.
+impl TryFrom<TinkoffInvestClient> for ProductionClient {
+    type Error = Error;
+
+    fn try_from(client: TinkoffInvestClient) -> Result<Self, Error> {
+        client.ensure_scope(TokenScope::Production)?;
+        Ok(Self { client: client })
+    }
+}
+
+delegate_client!(ProductionClient);
+delegate_market!(ProductionClient);
+delegate_orders!(ProductionClient);
+delegate_portfolio!(ProductionClient);
+delegate_operations!(ProductionClient);
+delegate_user!(ProductionClient);
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::orders::Orders;
+    use crate::token::{SecretToken, TokenScope};
+    use crate::{Environment, Error, TinkoffInvestClient, PRODUCTION_ENDPOINT, SANDBOX_ENDPOINT};
+
+    fn client(endpoint: &str, token: SecretToken) -> TinkoffInvestClient {
+        TinkoffInvestClient::builder(token)
+            .environment(Environment::Custom {
+                rest: endpoint.to_string(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn endpoint_guard() {
+        assert!(client(SANDBOX_ENDPOINT, "token123".into())
+            .into_production()
+            .is_err());
+        assert!(client(PRODUCTION_ENDPOINT, "token123".into())
+            .into_sandbox()
+            .is_err());
+
+        assert!(client(PRODUCTION_ENDPOINT, "token123".into())
+            .into_production()
+            .is_ok());
+        assert!(client(SANDBOX_ENDPOINT, "token123".into())
+            .into_sandbox()
+            .is_ok());
+        assert!(client("http://localhost:1234", "token123".into())
+            .into_production()
+            .is_ok());
+    }
+
+    #[test]
+    fn token_guard() {
+        let sandbox_token = SecretToken::new("token123").with_scope(TokenScope::Sandbox);
+        let production_token = SecretToken::new("token456").with_scope(TokenScope::Production);
+
+        assert!(client(PRODUCTION_ENDPOINT, sandbox_token.clone())
+            .into_production()
+            .is_err());
+
+        let production = client(PRODUCTION_ENDPOINT, production_token.clone())
+            .into_production()
+            .unwrap();
+        assert!(production.set_token(sandbox_token.clone()).is_err());
+        assert!(production.set_token(production_token.clone()).is_ok());
+
+        let sandbox = client(SANDBOX_ENDPOINT, sandbox_token)
+            .into_sandbox()
+            .unwrap();
+        assert!(sandbox.set_token(production_token).is_err());
+    }
+
+    #[tokio::test]
+    async fn untyped_client_guard() {
+        let sandbox_token = SecretToken::new("token123").with_scope(TokenScope::Sandbox);
+        let production_token = SecretToken::new("token456").with_scope(TokenScope::Production);
+        let figi = Figi::new("BBG000B9XRY4").unwrap();
+
+        // Refused before anything is sent to the live endpoint.
+        let untyped = client(PRODUCTION_ENDPOINT, sandbox_token.clone());
+        assert!(matches!(
+            untyped
+                .make_market_order(&figi, None, Operation::Buy, 1)
+                .await,
+            Err(Error::ConfigurationError { .. })
+        ));
+
+        let untyped = client(PRODUCTION_ENDPOINT, production_token.clone());
+        assert!(untyped.set_token(sandbox_token.clone()).is_err());
+        assert!(untyped.set_token(production_token.clone()).is_ok());
+
+        let read_only = client(SANDBOX_ENDPOINT, sandbox_token.clone()).into_read_only();
+        assert!(read_only.set_token(production_token).is_err());
+        assert!(read_only.set_token(sandbox_token).is_ok());
+    }
+}
@@ -0,0 +1,373 @@
+//! Messages of the streaming API.
+//!
+//! Event names and field casing follow the broker's streaming spec: events are
+//! tagged by `event`, and all fields, `request_id` included, are snake_case.
+//! `Ping`, `Pong`, `Binary` and `Close` stand for websocket frames and never
+//! appear in JSON.
+
+use crate::domain::{Amount, Figi, Interval};
+use chrono::{DateTime, FixedOffset};
+use serde_derive::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "event")]
+pub enum OutcomeEvent {
+    #[serde(rename = "candle:subscribe")]
+    CandleSubscribe {
+        figi: Figi,
+        interval: Interval,
+        request_id: Option<String>,
+    },
+    #[serde(rename = "candle:unsubscribe")]
+    CandleUnsubscribe {
+        figi: Figi,
+        interval: Interval,
+        request_id: Option<String>,
+    },
+    #[serde(rename = "orderbook:subscribe")]
+    OrderbookSubscribe {
+        figi: Figi,
+        depth: i32,
+        request_id: Option<String>,
+    },
+    #[serde(rename = "orderbook:unsubscribe")]
+    OrderbookUnsubscribe {
+        figi: Figi,
+        depth: i32,
+        request_id: Option<String>,
+    },
+    #[serde(rename = "instrument_info:subscribe")]
+    InstrumentInfoSubscribe {
+        figi: Figi,
+        request_id: Option<String>,
+    },
+    #[serde(rename = "instrument_info:unsubscribe")]
+    InstrumentInfoUnsubscribe {
+        figi: Figi,
+        request_id: Option<String>,
+    },
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    Ping(#[serde(default)] Vec<u8>),
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    Pong(#[serde(default)] Vec<u8>),
+}
+
+impl OutcomeEvent {
+    /// Value of the `event` tag, or the frame type for pings and pongs.
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutcomeEvent::CandleSubscribe { .. } => "candle:subscribe",
+            OutcomeEvent::CandleUnsubscribe { .. } => "candle:unsubscribe",
+            OutcomeEvent::OrderbookSubscribe { .. } => "orderbook:subscribe",
+            OutcomeEvent::OrderbookUnsubscribe { .. } => "orderbook:unsubscribe",
+            OutcomeEvent::InstrumentInfoSubscribe { .. } => "instrument_info:subscribe",
+            OutcomeEvent::InstrumentInfoUnsubscribe { .. } => "instrument_info:unsubscribe",
+            OutcomeEvent::Ping(_) => "ping",
+            OutcomeEvent::Pong(_) => "pong",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(tag = "event")]
+pub enum IncomeEvent {
+    #[serde(rename = "candle")]
+    Candle {
+        time: DateTime<FixedOffset>,
+        payload: CandleEventPayload,
+    },
+    #[serde(rename = "orderbook")]
+    OrderBook {
+        time: DateTime<FixedOffset>,
+        payload: OrderBookEventPayload,
+    },
+    #[serde(rename = "instrument_info")]
+    InstrumentInfo {
+        time: DateTime<FixedOffset>,
+        payload: InstrumentInfoEventPayload,
+    },
+    #[serde(rename = "error")]
+    Error {
+        time: DateTime<FixedOffset>,
+        payload: ErrorEventPayload,
+    },
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    Binary(#[serde(default)] Vec<u8>),
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    Ping(#[serde(default)] Vec<u8>),
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    Pong(#[serde(default)] Vec<u8>),
+    #[serde(skip_serializing)]
+    #[serde(skip_deserializing)]
+    Close,
+}
+
+impl IncomeEvent {
+    /// Value of the `event` tag, or the frame type for the other variants.
+    pub fn name(&self) -> &'static str {
+        match self {
+            IncomeEvent::Candle { .. } => "candle",
+            IncomeEvent::OrderBook { .. } => "orderbook",
+            IncomeEvent::InstrumentInfo { .. } => "instrument_info",
+            IncomeEvent::Error { .. } => "error",
+            IncomeEvent::Binary(_) => "binary",
+            IncomeEvent::Ping(_) => "ping",
+            IncomeEvent::Pong(_) => "pong",
+            IncomeEvent::Close => "close",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CandleEventPayload {
+    pub o: Amount,
+    pub c: Amount,
+    pub h: Amount,
+    pub l: Amount,
+    pub v: f64,
+    pub time: DateTime<FixedOffset>,
+    pub interval: Interval,
+    pub figi: Figi,
+}
+
+/// Order book from the stream. Unlike the REST order book, bids and asks are
+/// `[price, quantity]` pairs.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OrderBookEventPayload {
+    pub figi: Figi,
+    pub depth: i32,
+    #[serde(default)]
+    pub bids: Vec<(Amount, f64)>,
+    #[serde(default)]
+    pub asks: Vec<(Amount, f64)>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct InstrumentInfoEventPayload {
+    pub trade_status: String,
+    pub min_price_increment: Amount,
+    pub lot: f64,
+    pub accrued_interest: Option<Amount>,
+    pub limit_up: Option<Amount>,
+    pub limit_down: Option<Amount>,
+    pub figi: Figi,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ErrorEventPayload {
+    pub error: String,
+    pub request_id: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::{Amount, Figi, Interval};
+    use crate::protocol::*;
+    use serde_json::Value;
+
+    fn fixture(json: &str) -> Value {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn decode(json: &str) -> IncomeEvent {
+        serde_json::from_str(json).unwrap()
+    }
+
+    fn amount(value: &str) -> Amount {
+        value.parse().unwrap()
+    }
+
+    /// Field names of an object and of its `payload`, so casing regressions show up
+    /// even where numbers don't survive a round trip byte for byte.
+    fn fields(value: &Value) -> Vec<String> {
+        let mut fields = vec![];
+
+        for (name, field) in value.as_object().unwrap() {
+            fields.push(name.clone());
+            if name == "payload" {
+                fields.extend(
+                    field
+                        .as_object()
+                        .unwrap()
+                        .keys()
+                        .map(|k| format!("payload.{}", k)),
+                );
+            }
+        }
+
+        fields.sort();
+        fields
+    }
+
+    #[test]
+    fn outcome_events() {
+        let figi = Figi::new("BBG0013HGFT4").unwrap();
+        let request_id = |id: &str| Some(id.to_string());
+
+        let events = vec![
+            (
+                include_str!("../fixtures/streaming/candle_subscribe.json"),
+                OutcomeEvent::CandleSubscribe {
+                    figi: figi.clone(),
+                    interval: Interval::_5min,
+                    request_id: request_id("r1"),
+                },
+            ),
+            (
+                include_str!("../fixtures/streaming/candle_unsubscribe.json"),
+                OutcomeEvent::CandleUnsubscribe {
+                    figi: figi.clone(),
+                    interval: Interval::_5min,
+                    request_id: request_id("r1"),
+                },
+            ),
+            (
+                include_str!("../fixtures/streaming/orderbook_subscribe.json"),
+                OutcomeEvent::OrderbookSubscribe {
+                    figi: figi.clone(),
+                    depth: 10,
+                    request_id: request_id("r2"),
+                },
+            ),
+            (
+                include_str!("../fixtures/streaming/orderbook_unsubscribe.json"),
+                OutcomeEvent::OrderbookUnsubscribe {
+                    figi: figi.clone(),
+                    depth: 10,
+                    request_id: request_id("r2"),
+                },
+            ),
+            (
+                include_str!("../fixtures/streaming/instrument_info_subscribe.json"),
+                OutcomeEvent::InstrumentInfoSubscribe {
+                    figi: figi.clone(),
+                    request_id: request_id("r3"),
+                },
+            ),
+            (
+                include_str!("../fixtures/streaming/instrument_info_unsubscribe.json"),
+                OutcomeEvent::InstrumentInfoUnsubscribe {
+                    figi: figi.clone(),
+                    request_id: request_id("r3"),
+                },
+            ),
+        ];
+
+        for (json, event) in events {
+            let expected = fixture(json);
+
+            assert_eq!(serde_json::to_value(&event).unwrap(), expected);
+            assert_eq!(expected["event"], event.name());
+
+            let decoded: OutcomeEvent = serde_json::from_value(expected.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&decoded).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn candle() {
+        let json = include_str!("../fixtures/streaming/candle.json");
+
+        let event = decode(json);
+        assert_eq!(
+            fields(&serde_json::to_value(&event).unwrap()),
+            fields(&fixture(json))
+        );
+        assert_eq!(fixture(json)["event"], event.name());
+
+        match event {
+            IncomeEvent::Candle { payload, .. } => {
+                assert_eq!(payload.o, amount("64.0575"));
+                assert_eq!(payload.c, amount("64.0601"));
+                assert_eq!(payload.h, amount("64.0602"));
+                assert_eq!(payload.l, amount("64.0575"));
+                assert_eq!(payload.v, 156.0);
+                assert_eq!(payload.interval, Interval::_5min);
+                assert_eq!(payload.figi, "BBG0013HGFT4");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn orderbook() {
+        let json = include_str!("../fixtures/streaming/orderbook.json");
+
+        let event = decode(json);
+        assert_eq!(
+            fields(&serde_json::to_value(&event).unwrap()),
+            fields(&fixture(json))
+        );
+        assert_eq!(fixture(json)["event"], event.name());
+
+        match event {
+            IncomeEvent::OrderBook { payload, .. } => {
+                assert_eq!(payload.figi, "BBG0013HGFT4");
+                assert_eq!(payload.depth, 2);
+                assert_eq!(
+                    payload.bids,
+                    vec![(amount("64.3525"), 204.0), (amount("64.1975"), 202.0)]
+                );
+                assert_eq!(
+                    payload.asks,
+                    vec![(amount("64.38"), 2.0), (amount("64.4"), 200.0)]
+                );
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn instrument_info() {
+        let json = include_str!("../fixtures/streaming/instrument_info.json");
+
+        let event = decode(json);
+        assert_eq!(
+            fields(&serde_json::to_value(&event).unwrap()),
+            fields(&fixture(json))
+        );
+        assert_eq!(fixture(json)["event"], event.name());
+
+        match event {
+            IncomeEvent::InstrumentInfo { payload, .. } => {
+                assert_eq!(payload.trade_status, "normal_trading");
+                assert_eq!(payload.min_price_increment, amount("0.0025"));
+                assert_eq!(payload.lot, 1000.0);
+                assert_eq!(payload.accrued_interest, Some(amount("8.2")));
+                assert_eq!(payload.limit_up, Some(amount("70.5")));
+                assert_eq!(payload.limit_down, Some(amount("58.1")));
+                assert_eq!(payload.figi, "BBG0013HGFT4");
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn error() {
+        let json = include_str!("../fixtures/streaming/error.json");
+
+        let event = decode(json);
+        assert_eq!(
+            fields(&serde_json::to_value(&event).unwrap()),
+            fields(&fixture(json))
+        );
+        assert_eq!(fixture(json)["event"], event.name());
+
+        match event {
+            IncomeEvent::Error { payload, .. } => {
+                assert_eq!(
+                    payload.error,
+                    "Subscription instrument_info:subscribe. FIGI NOOOOOOO not found"
+                );
+                assert_eq!(payload.request_id.as_deref(), Some("r3"));
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,350 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Group of REST endpoints sharing one request quota on the broker side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EndpointGroup {
+    Market,
+    Orders,
+    Portfolio,
+    Operations,
+    Sandbox,
+    User,
+}
+
+impl EndpointGroup {
+    pub const ALL: [EndpointGroup; 6] = [
+        EndpointGroup::Market,
+        EndpointGroup::Orders,
+        EndpointGroup::Portfolio,
+        EndpointGroup::Operations,
+        EndpointGroup::Sandbox,
+        EndpointGroup::User,
+    ];
+
+    pub(crate) fn from_uri(uri: &str) -> Self {
+        let prefix = uri.trim_start_matches('/').split('/').next().unwrap_or("");
+
+        match prefix {
+            "orders" => EndpointGroup::Orders,
+            "portfolio" => EndpointGroup::Portfolio,
+            "operations" => EndpointGroup::Operations,
+            "sandbox" => EndpointGroup::Sandbox,
+            "user" => EndpointGroup::User,
+            _ => EndpointGroup::Market,
+        }
+    }
+}
+
+/// Allows `requests` requests per `period`, refilled continuously.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Quota {
+    pub requests: u32,
+    pub period: Duration,
+}
+
+impl Quota {
+    /// # Panics
+    ///
+    /// Panics if `requests` is zero.
+    pub fn per_minute(requests: u32) -> Self {
+        assert!(requests > 0, "quota must allow at least one request");
+
+        Self {
+            requests,
+            period: Duration::from_secs(60),
+        }
+    }
+
+    /// Broker's published per-minute quota for the group.
+    pub fn default_for(group: EndpointGroup) -> Self {
+        match group {
+            EndpointGroup::Market => Quota::per_minute(240),
+            EndpointGroup::Orders => Quota::per_minute(100),
+            EndpointGroup::Portfolio => Quota::per_minute(120),
+            EndpointGroup::Operations => Quota::per_minute(120),
+            EndpointGroup::Sandbox => Quota::per_minute(120),
+            EndpointGroup::User => Quota::per_minute(100),
+        }
+    }
+}
+
+/// Point-in-time state of one endpoint group's bucket.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimiterState {
+    pub quota: Quota,
+    /// Requests that can be sent right now without waiting.
+    pub available: f64,
+    /// Requests let through since the limiter was created.
+    pub acquired: u64,
+    /// Requests that had to wait for capacity since the limiter was created.
+    pub throttled: u64,
+}
+
+#[derive(Debug)]
+struct Bucket {
+    quota: Quota,
+    tokens: f64,
+    refilled_at: Instant,
+    acquired: u64,
+    throttled: u64,
+}
+
+impl Bucket {
+    fn new(quota: Quota) -> Self {
+        Self {
+            quota,
+            tokens: quota.requests as f64,
+            refilled_at: Instant::now(),
+            acquired: 0,
+            throttled: 0,
+        }
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        let capacity = self.quota.requests as f64;
+        let rate = capacity / self.quota.period.as_secs_f64();
+
+        self.tokens = (self.tokens + elapsed * rate).min(capacity);
+        self.refilled_at = now;
+    }
+
+    /// Takes a token, or returns how long to wait until one is available.
+    fn try_acquire(&mut self) -> Result<(), Duration> {
+        self.refill();
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            self.acquired += 1;
+            Ok(())
+        } else {
+            let rate = self.quota.requests as f64 / self.quota.period.as_secs_f64();
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / rate))
+        }
+    }
+
+    fn state(&mut self) -> RateLimiterState {
+        self.refill();
+
+        RateLimiterState {
+            quota: self.quota,
+            available: self.tokens,
+            acquired: self.acquired,
+            throttled: self.throttled,
+        }
+    }
+}
+
+/// Client-side token bucket limiter keyed by [`EndpointGroup`].
+///
+/// Requests wait for capacity instead of failing with HTTP 429.
+/// One limiter can be shared by several clients using the same token.
+#[derive(Debug)]
+pub struct RateLimiter {
+    buckets: Mutex<HashMap<EndpointGroup, Bucket>>,
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RateLimiter {
+    /// Limiter with the broker's default quotas for every group.
+    pub fn new() -> Self {
+        let buckets = EndpointGroup::ALL
+            .iter()
+            .map(|g| (*g, Bucket::new(Quota::default_for(*g))))
+            .collect();
+
+        Self {
+            buckets: Mutex::new(buckets),
+        }
+    }
+
+    /// # Panics
+    ///
+    /// Panics if `quota` allows no requests or has a zero period, as no request
+    /// could ever be sent.
+    pub fn with_quota(self, group: EndpointGroup, quota: Quota) -> Self {
+        assert!(
+            quota.requests > 0 && !quota.period.is_zero(),
+            "quota must allow at least one request per non-zero period"
+        );
+
+        self.buckets
+            .lock()
+            .unwrap()
+            .insert(group, Bucket::new(quota));
+        self
+    }
+
+    /// Waits until a request to `group` fits into its quota.
+    pub async fn acquire(&self, group: EndpointGroup) {
+        let mut throttled = false;
+
+        loop {
+            let wait = {
+                let mut buckets = self.buckets.lock().unwrap();
+                let bucket = buckets.get_mut(&group).unwrap();
+
+                match bucket.try_acquire() {
+                    Ok(()) => {
+                        if throttled {
+                            bucket.throttled += 1;
+                        }
+                        return;
+                    }
+                    Err(wait) => wait,
+                }
+            };
+
+            throttled = true;
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    pub fn state(&self, group: EndpointGroup) -> RateLimiterState {
+        self.buckets
+            .lock()
+            .unwrap()
+            .get_mut(&group)
+            .unwrap()
+            .state()
+    }
+
+    pub fn snapshot(&self) -> HashMap<EndpointGroup, RateLimiterState> {
+        self.buckets
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .map(|(group, bucket)| (*group, bucket.state()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::rate_limit::{EndpointGroup, Quota, RateLimiter};
+    use crate::user::User;
+    use crate::{Environment, TinkoffInvestClient};
+    use mockito::Matcher;
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn endpoint_group() {
+        assert_eq!(
+            EndpointGroup::from_uri("/market/candles"),
+            EndpointGroup::Market
+        );
+        assert_eq!(
+            EndpointGroup::from_uri("/orders/limit-order"),
+            EndpointGroup::Orders
+        );
+        assert_eq!(
+            EndpointGroup::from_uri("/portfolio/currencies"),
+            EndpointGroup::Portfolio
+        );
+        assert_eq!(
+            EndpointGroup::from_uri("/operations"),
+            EndpointGroup::Operations
+        );
+        assert_eq!(
+            EndpointGroup::from_uri("/sandbox/register"),
+            EndpointGroup::Sandbox
+        );
+        assert_eq!(
+            EndpointGroup::from_uri("/user/accounts"),
+            EndpointGroup::User
+        );
+    }
+
+    #[tokio::test]
+    async fn waits_for_capacity() {
+        let quota = Quota {
+            requests: 2,
+            period: Duration::from_millis(200),
+        };
+        let limiter = RateLimiter::new().with_quota(EndpointGroup::Market, quota);
+
+        let started = Instant::now();
+        limiter.acquire(EndpointGroup::Market).await;
+        limiter.acquire(EndpointGroup::Market).await;
+        assert!(started.elapsed() < Duration::from_millis(50));
+
+        limiter.acquire(EndpointGroup::Market).await;
+        assert!(started.elapsed() >= Duration::from_millis(90));
+
+        let state = limiter.state(EndpointGroup::Market);
+        assert_eq!(state.quota, quota);
+        assert_eq!(state.acquired, 3);
+        assert_eq!(state.throttled, 1);
+
+        let other = limiter.state(EndpointGroup::Orders);
+        assert_eq!(other.acquired, 0);
+        assert_eq!(other.available, 100.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request")]
+    fn rejects_zero_requests() {
+        let quota = Quota {
+            requests: 0,
+            period: Duration::from_secs(60),
+        };
+        RateLimiter::new().with_quota(EndpointGroup::Market, quota);
+    }
+
+    #[test]
+    #[should_panic(expected = "non-zero period")]
+    fn rejects_zero_period() {
+        let quota = Quota {
+            requests: 10,
+            period: Duration::from_secs(0),
+        };
+        RateLimiter::new().with_quota(EndpointGroup::Market, quota);
+    }
+
+    #[test]
+    #[should_panic(expected = "at least one request")]
+    fn per_minute_rejects_zero() {
+        Quota::per_minute(0);
+    }
+
+    #[tokio::test]
+    async fn client_uses_limiter() {
+        let mock = mockito::mock("GET", "/user/accounts")
+            .match_query(Matcher::Missing)
+            .with_body(
+                "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"accounts\": []}}",
+            )
+            .expect(2)
+            .create();
+
+        let limiter = Arc::new(RateLimiter::new());
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .rate_limiter(limiter.clone())
+            .build()
+            .unwrap();
+
+        tinkoff.accounts().await.unwrap();
+        tinkoff.accounts().await.unwrap();
+
+        let snapshot = tinkoff.rate_limiter().unwrap().snapshot();
+        assert_eq!(snapshot[&EndpointGroup::User].acquired, 2);
+        assert_eq!(snapshot[&EndpointGroup::Market].acquired, 0);
+
+        mock.assert();
+    }
+}
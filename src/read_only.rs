@@ -0,0 +1,81 @@
+use crate::errors::Error;
+use crate::token::SecretToken;
+use crate::TinkoffInvestClient;
+
+/// Client that can read market data and account state but can't trade.
+///
+/// It implements [`Market`], [`Portfolio`], [`Operations`] and [`User`] only,
+/// so order placement doesn't compile:
+///
+/// ```compile_fail
+/// use tinkoff_invest_client::{domain::Operation, Orders, TinkoffInvestClient};
+///
+/// async fn trade(client: TinkoffInvestClient) {
+///     let client = client.into_read_only();
+///     let figi = "BBG000B9XRY4".parse().unwrap();
+///     client.make_market_order(&figi, None, Operation::Buy, 1).await;
+/// }
+/// ```
+pub struct ReadOnlyClient {
+    client: TinkoffInvestClient,
+}
+
+impl ReadOnlyClient {
+    /// Replaces the token used by all following requests and streaming connections.
+    /// Fails for a token scoped to the environment the endpoint doesn't belong to.
+    pub fn set_token(&self, token: SecretToken) -> Result<(), Error> {
+        self.client.set_token(token)
+    }
+}
+
+impl From<TinkoffInvestClient> for ReadOnlyClient {
+    fn from(client: TinkoffInvestClient) -> Self {
+        Self { client: client }
+    }
+}
+
+delegate_client!(ReadOnlyClient);
+delegate_market!(ReadOnlyClient);
+delegate_portfolio!(ReadOnlyClient);
+delegate_operations!(ReadOnlyClient);
+delegate_user!(ReadOnlyClient);
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::portfolio::Portfolio;
+    use crate::TinkoffInvestClient;
+    use mockito::Matcher;
+
+    #[tokio::test]
+    async fn portfolio() {
+        let mock = mockito::mock("GET", "/portfolio")
+            .match_header("Authorization", "Bearer token123")
+            .match_query(Matcher::UrlEncoded(
+                "brokerAccountId".to_string(),
+                "account_123".to_string(),
+            ))
+            .with_body(
+                "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"positions\": []}}",
+            )
+            .expect(2)
+            .create();
+
+        let endpoint = &mockito::server_url();
+        let tinkoff =
+            TinkoffInvestClient::new(reqwest::Client::new(), endpoint, "token123").into_read_only();
+
+        tinkoff
+            .portfolio(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .await
+            .unwrap();
+        tinkoff
+            .account(&BrokerAccountId::new("account_123").unwrap())
+            .portfolio()
+            .await
+            .unwrap();
+
+        mock.assert();
+    }
+}
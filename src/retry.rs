@@ -0,0 +1,220 @@
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Retry policy for REST requests that failed with a retryable error
+/// (see [`Error::is_retryable`](crate::Error::is_retryable)).
+///
+/// GET requests are retried automatically. POST requests, including order placement,
+/// are only retried when [`RetryPolicy::retry_non_idempotent`] is enabled.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(10),
+            jitter: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled on every following one.
+    pub fn base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Upper bound of every delay, `Retry-After` from the server included.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Randomize every backoff delay between half and the full value.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Also retry POST requests. Such a retry can place the same order twice.
+    pub fn retry_non_idempotent(mut self, retry_non_idempotent: bool) -> Self {
+        self.retry_non_idempotent = retry_non_idempotent;
+        self
+    }
+
+    pub(crate) fn allows(&self, idempotent: bool, attempt: u32) -> bool {
+        (idempotent || self.retry_non_idempotent) && attempt < self.max_attempts
+    }
+
+    /// Delay before the retry following `attempt`; `Retry-After` from the server wins,
+    /// but is capped at `max_delay` as well.
+    pub(crate) fn delay(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+
+        if self.jitter {
+            let random = RandomState::new().build_hasher().finish();
+            let half = delay / 2;
+            half + half.mul_f64((random % 1000) as f64 / 1000.0)
+        } else {
+            delay
+        }
+    }
+}
+
+/// Parses a `Retry-After` header given in seconds.
+pub(crate) fn parse_retry_after(value: &str) -> Option<Duration> {
+    value.trim().parse::<u64>().ok().map(Duration::from_secs)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::orders::Orders;
+    use crate::retry::{parse_retry_after, RetryPolicy};
+    use crate::user::User;
+    use crate::{Environment, TinkoffInvestClient};
+    use mockito::Matcher;
+    use std::time::Duration;
+
+    fn client(policy: RetryPolicy) -> TinkoffInvestClient {
+        TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .retry_policy(policy)
+            .build()
+            .unwrap()
+    }
+
+    fn fast_policy() -> RetryPolicy {
+        RetryPolicy::default()
+            .max_attempts(3)
+            .base_delay(Duration::from_millis(1))
+            .jitter(false)
+    }
+
+    #[test]
+    fn delay() {
+        let policy = RetryPolicy::default()
+            .base_delay(Duration::from_millis(100))
+            .max_delay(Duration::from_millis(300))
+            .jitter(false);
+
+        assert_eq!(policy.delay(1, None), Duration::from_millis(100));
+        assert_eq!(policy.delay(2, None), Duration::from_millis(200));
+        assert_eq!(policy.delay(3, None), Duration::from_millis(300));
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_millis(250))),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            policy.delay(1, Some(Duration::from_secs(86400))),
+            Duration::from_millis(300)
+        );
+
+        let jittered = policy.jitter(true).delay(2, None);
+        assert!(jittered >= Duration::from_millis(100) && jittered <= Duration::from_millis(200));
+    }
+
+    #[test]
+    fn retry_after() {
+        assert_eq!(parse_retry_after("3"), Some(Duration::from_secs(3)));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn retries_get_requests() {
+        let failure = mockito::mock("GET", "/user/accounts")
+            .match_query(Matcher::Missing)
+            .with_status(503)
+            .with_body("Service Unavailable")
+            .expect(2)
+            .create();
+        let success = mockito::mock("GET", "/user/accounts")
+            .match_query(Matcher::Missing)
+            .with_body(
+                "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"accounts\": []}}",
+            )
+            .expect(1)
+            .create();
+
+        client(fast_policy()).accounts().await.unwrap();
+
+        failure.assert();
+        success.assert();
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let failure = mockito::mock("GET", "/user/accounts")
+            .match_query(Matcher::Missing)
+            .with_status(429)
+            .with_header("Retry-After", "0")
+            .with_body("{\"trackingId\": \"tracking_id_0\",\"status\": \"Error\",\"payload\": {}}")
+            .expect(3)
+            .create();
+
+        let error = client(fast_policy()).accounts().await.unwrap_err();
+        assert!(error.is_retryable());
+
+        failure.assert();
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_orders() {
+        let failure = mockito::mock("POST", "/orders/market-order")
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .with_body("Service Unavailable")
+            .expect(1)
+            .create();
+
+        client(fast_policy())
+            .make_market_order(&Figi::new("BBG000B9XRY4").unwrap(), None, Operation::Buy, 1)
+            .await
+            .unwrap_err();
+
+        failure.assert();
+    }
+
+    #[tokio::test]
+    async fn retries_orders_when_enabled() {
+        let failure = mockito::mock("POST", "/orders/market-order")
+            .match_query(Matcher::Any)
+            .with_status(503)
+            .with_body("Service Unavailable")
+            .expect(3)
+            .create();
+
+        client(fast_policy().retry_non_idempotent(true))
+            .make_market_order(&Figi::new("BBG000B9XRY4").unwrap(), None, Operation::Buy, 1)
+            .await
+            .unwrap_err();
+
+        failure.assert();
+    }
+}
@@ -1,5 +1,7 @@
 use crate::domain::*;
 use crate::errors::Error;
+use crate::read_only::ReadOnlyClient;
+use crate::token::{SecretToken, TokenScope};
 use crate::TinkoffInvestClient;
 use async_trait::async_trait;
 use std::collections::HashMap;
@@ -13,29 +15,77 @@ pub trait Sandbox {
 
     async fn set_currencies_balance(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
         currency: Currency,
-        balance: f64,
+        balance: Amount,
     ) -> Result<Response<EmptyPayload>, Error>;
 
     async fn set_positions_balance(
         &self,
-        broker_account_id: Option<&str>,
-        figi: &str,
-        balance: f64,
+        broker_account_id: Option<&BrokerAccountId>,
+        figi: &Figi,
+        balance: Amount,
     ) -> Result<Response<EmptyPayload>, Error>;
 
     async fn remove(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<EmptyPayload>, Error>;
 
-    async fn clear(&self, broker_account_id: Option<&str>)
-        -> Result<Response<EmptyPayload>, Error>;
+    async fn clear(
+        &self,
+        broker_account_id: Option<&BrokerAccountId>,
+    ) -> Result<Response<EmptyPayload>, Error>;
+}
+
+/// Client bound to the sandbox. It's the only client implementing [`Sandbox`],
+/// so sandbox calls can't reach a live account:
+///
+/// ```compile_fail
+/// use tinkoff_invest_client::{Sandbox, TinkoffInvestClient};
+///
+/// async fn reset(client: TinkoffInvestClient) {
+///     let client = client.into_production().unwrap();
+///     client.clear(None).await;
+/// }
+/// ```
+pub struct SandboxClient {
+    client: TinkoffInvestClient,
 }
 
+impl SandboxClient {
+    /// Replaces the token used by all following requests and streaming connections.
+    /// Fails for a token scoped to production.
+    pub fn set_token(&self, token: SecretToken) -> Result<(), Error> {
+        token.ensure_scope(TokenScope::Sandbox)?;
+        self.client.set_token(token)
+    }
+
+    /// Downgrades into a client that can't place or cancel orders.
+    pub fn into_read_only(self) -> ReadOnlyClient {
+        ReadOnlyClient::from(self.client)
+    }
+}
+
+/// Fails when the client points to the production endpoint or has a production token.
+impl TryFrom<TinkoffInvestClient> for SandboxClient {
+    type Error = Error;
+
+    fn try_from(client: TinkoffInvestClient) -> Result<Self, Error> {
+        client.ensure_scope(TokenScope::Sandbox)?;
+        Ok(Self { client: client })
+    }
+}
+
+delegate_client!(SandboxClient);
+delegate_market!(SandboxClient);
+delegate_orders!(SandboxClient);
+delegate_portfolio!(SandboxClient);
+delegate_operations!(SandboxClient);
+delegate_user!(SandboxClient);
+
 #[async_trait]
-impl Sandbox for TinkoffInvestClient {
+impl Sandbox for SandboxClient {
     async fn register(
         &self,
         broker_account_type: BrokerAccountType,
@@ -48,75 +98,82 @@ impl Sandbox for TinkoffInvestClient {
 
         let query = HashMap::new();
 
-        self.make_post_request("/sandbox/register", &query, &body)
+        self.client
+            .make_post_request("/sandbox/register", &query, &body)
             .await
     }
 
     async fn set_currencies_balance(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
         currency: Currency,
-        balance: f64,
+        balance: Amount,
     ) -> Result<Response<EmptyPayload>, Error> {
         let request = SandboxRequest::SetCurrenciesBalance { currency, balance };
 
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
         let body = serde_json::to_string(&request)?;
 
-        self.make_post_request("/sandbox/currencies/balance", &query, &body)
+        self.client
+            .make_post_request("/sandbox/currencies/balance", &query, &body)
             .await
     }
 
     async fn set_positions_balance(
         &self,
-        broker_account_id: Option<&str>,
-        figi: &str,
-        balance: f64,
+        broker_account_id: Option<&BrokerAccountId>,
+        figi: &Figi,
+        balance: Amount,
     ) -> Result<Response<EmptyPayload>, Error> {
-        let figi = figi.to_string();
+        let figi = figi.clone();
 
         let request = SandboxRequest::SetPositionBalance { figi, balance };
 
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
         let body = serde_json::to_string(&request)?;
 
-        self.make_post_request("/sandbox/positions/balance", &query, &body)
+        self.client
+            .make_post_request("/sandbox/positions/balance", &query, &body)
             .await
     }
 
     async fn remove(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<EmptyPayload>, Error> {
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
-        self.make_post_request("/sandbox/remove", &query, "").await
+        self.client
+            .make_post_request("/sandbox/remove", &query, "")
+            .await
     }
 
     async fn clear(
         &self,
-        broker_account_id: Option<&str>,
+        broker_account_id: Option<&BrokerAccountId>,
     ) -> Result<Response<EmptyPayload>, Error> {
         let mut query = HashMap::new();
 
         if let Some(account) = broker_account_id {
-            query.insert("brokerAccountId", account);
+            query.insert("brokerAccountId", account.as_str());
         }
 
-        self.make_post_request("/sandbox/clear", &query, "").await
+        self.client
+            .make_post_request("/sandbox/clear", &query, "")
+            .await
     }
 }
 
@@ -126,7 +183,6 @@ mod tests {
     use crate::domain::*;
     use crate::sandbox::Sandbox;
     use crate::TinkoffInvestClient;
-    use mockito;
     use mockito::Matcher;
 
     #[tokio::test]
@@ -155,7 +211,9 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token)
+            .into_sandbox()
+            .unwrap();
 
         tinkoff.register(BrokerAccountType::Tinkoff).await.unwrap();
 
@@ -182,10 +240,16 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token)
+            .into_sandbox()
+            .unwrap();
 
         tinkoff
-            .set_currencies_balance(Some("account_123"), Currency::RUB, 1000.0)
+            .set_currencies_balance(
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+                Currency::RUB,
+                "1000.0".parse().unwrap(),
+            )
             .await
             .unwrap();
 
@@ -205,17 +269,23 @@ mod tests {
             .match_header("content-length", Matcher::Any)
             .match_header("host", Matcher::Any)
             .match_body(Matcher::JsonString(
-                "{\"figi\":\"some_figi\",\"balance\":1000.0}".to_string(),
+                "{\"figi\":\"BBG00475KKY8\",\"balance\":1000.0}".to_string(),
             ))
             .with_body("{\"trackingId\": \"some_tracking_id\",\"payload\": {},\"status\": \"Ok\"}")
             .create();
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token)
+            .into_sandbox()
+            .unwrap();
 
         tinkoff
-            .set_positions_balance(Some("account_123"), "some_figi", 1000.0)
+            .set_positions_balance(
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+                &Figi::new("BBG00475KKY8").unwrap(),
+                "1000.0".parse().unwrap(),
+            )
             .await
             .unwrap();
 
@@ -239,9 +309,14 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token)
+            .into_sandbox()
+            .unwrap();
 
-        tinkoff.remove(Some("account_123")).await.unwrap();
+        tinkoff
+            .remove(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .await
+            .unwrap();
 
         mock.assert();
     }
@@ -263,9 +338,14 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token)
+            .into_sandbox()
+            .unwrap();
 
-        tinkoff.clear(Some("account_123")).await.unwrap();
+        tinkoff
+            .clear(Some(&BrokerAccountId::new("account_123").unwrap()))
+            .await
+            .unwrap();
 
         mock.assert();
     }
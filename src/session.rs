@@ -0,0 +1,1002 @@
+use crate::domain::*;
+use crate::errors::{Error, ErrorKind};
+use crate::retry::RetryPolicy;
+use crate::subscription::{
+    assign_request_id, Ack, Acknowledgement, Command, Registry, Route, Subscription,
+    SubscriptionStream,
+};
+#[cfg(feature = "metrics")]
+use crate::telemetry;
+use crate::token::SecretToken;
+use futures::sink::{Sink, SinkExt};
+use futures::stream::{Stream, StreamExt};
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::time::{self, Instant, MissedTickBehavior};
+
+type EventSink = Pin<Box<dyn Sink<OutcomeEvent, Error = Error> + Send>>;
+type EventStream = Pin<Box<dyn Stream<Item = Result<IncomeEvent, Error>> + Send>>;
+
+/// Opens streaming connections with the token the client has at that moment,
+/// so reconnects pick up a rotated token.
+pub(crate) struct Connector {
+    ws_endpoint: String,
+    token: Arc<RwLock<SecretToken>>,
+}
+
+impl Connector {
+    pub(crate) fn new(ws_endpoint: String, token: Arc<RwLock<SecretToken>>) -> Self {
+        Self {
+            ws_endpoint: ws_endpoint,
+            token: token,
+        }
+    }
+
+    async fn connect(&self) -> Result<(EventSink, EventStream), Error> {
+        let bearer = self.token.read().unwrap().bearer();
+        let (sink, stream) = crate::open_stream(self.ws_endpoint.clone(), bearer).await?;
+
+        Ok((Box::pin(sink), Box::pin(stream)))
+    }
+}
+
+/// Event reported by a [`StreamingSession`].
+#[derive(Debug)]
+pub enum SessionEvent {
+    /// Message from the server. Transport level pings and pongs aren't reported.
+    Event(IncomeEvent),
+    /// Message that couldn't be decoded. The connection stays open.
+    Error(Error),
+    /// The connection was lost. The session reconnects unless the reconnect policy
+    /// is exhausted, in which case the stream ends after this event.
+    Disconnected(Error),
+    /// The connection was restored and all active subscriptions were sent again.
+    Reconnected,
+    /// The session wasn't polled fast enough and this many events were dropped.
+    Lagged(u64),
+}
+
+/// Options of a [`StreamingSession`].
+#[derive(Debug, Clone)]
+pub struct SessionConfig {
+    reconnect_policy: RetryPolicy,
+    buffer: usize,
+    keepalive: bool,
+    ping_interval: Duration,
+    stale_timeout: Duration,
+    ack_timeout: Duration,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            reconnect_policy: RetryPolicy::default().max_attempts(u32::MAX),
+            buffer: 1024,
+            keepalive: true,
+            ping_interval: Duration::from_secs(15),
+            stale_timeout: Duration::from_secs(45),
+            ack_timeout: Duration::from_secs(5),
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Backoff between reconnect attempts and how many consecutive attempts are made
+    /// before giving up. By default the session keeps reconnecting forever.
+    pub fn reconnect_policy(mut self, reconnect_policy: RetryPolicy) -> Self {
+        self.reconnect_policy = reconnect_policy;
+        self
+    }
+
+    /// Events kept for a slow consumer, separately for the session and for every
+    /// subscription stream. The socket is read regardless: session events that don't
+    /// fit are dropped and counted in [`SessionEvent::Lagged`], and a subscription
+    /// stream that falls further behind is ended.
+    pub fn buffer(mut self, buffer: usize) -> Self {
+        self.buffer = buffer.max(1);
+        self
+    }
+
+    /// Whether to ping the server and drop connections that went silent. Enabled by default.
+    pub fn keepalive(mut self, keepalive: bool) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// How often a ping is sent while the keepalive is enabled.
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    /// How long a connection may stay without any incoming message, pongs included,
+    /// before it's considered dead and reconnected with a
+    /// [`StaleConnectionError`](Error::StaleConnectionError).
+    pub fn stale_timeout(mut self, stale_timeout: Duration) -> Self {
+        self.stale_timeout = stale_timeout;
+        self
+    }
+
+    /// How long an [`Acknowledgement`] waits for a rejection or the first event
+    /// before it takes the subscription as accepted. 5 seconds by default.
+    pub fn ack_timeout(mut self, ack_timeout: Duration) -> Self {
+        self.ack_timeout = ack_timeout;
+        self
+    }
+}
+
+/// Streaming connection that survives network failures.
+///
+/// A background task owns the websocket. It remembers candle, order book and
+/// instrument info subscriptions sent through [`StreamingSession::send`],
+/// reconnects with backoff when the connection drops and sends the active
+/// subscriptions again. Half-open connections are detected by the keepalive,
+/// see [`SessionConfig::keepalive`].
+///
+/// Several subscriptions share the connection. The `subscribe_*` methods return a
+/// stream per subscription, other events are yielded by the session itself. The
+/// connection is closed once the session and all subscription streams are dropped.
+pub struct StreamingSession {
+    commands: mpsc::UnboundedSender<Command>,
+    events: mpsc::Receiver<SessionEvent>,
+    registry: Arc<Mutex<Registry>>,
+    next_request_id: AtomicU64,
+    buffer: usize,
+    ack_timeout: Duration,
+}
+
+impl StreamingSession {
+    pub(crate) async fn connect(
+        connector: Connector,
+        config: SessionConfig,
+    ) -> Result<Self, Error> {
+        let connection = connector.connect().await?;
+
+        let (commands, commands_rx) = mpsc::unbounded_channel();
+        let (events_tx, events) = mpsc::channel(config.buffer);
+        let buffer = config.buffer;
+        let ack_timeout = config.ack_timeout;
+
+        let registry = Arc::new(Mutex::new(Registry::default()));
+
+        tokio::spawn(run(
+            connector,
+            config,
+            connection,
+            commands_rx,
+            events_tx,
+            registry.clone(),
+        ));
+
+        Ok(Self {
+            commands: commands,
+            events: events,
+            registry: registry,
+            next_request_id: AtomicU64::new(1),
+            buffer: buffer,
+            ack_timeout: ack_timeout,
+        })
+    }
+
+    /// Sends a subscription or an unsubscription. Active subscriptions are restored after reconnecting.
+    /// A missing `request_id` is assigned by the session.
+    pub fn send(&self, event: OutcomeEvent) -> Result<(), Error> {
+        self.command(event, None, None)
+    }
+
+    /// Like [`send`](Self::send), but returns an [`Acknowledgement`] that tells whether
+    /// the server accepted the subscription.
+    pub fn subscribe(&self, event: OutcomeEvent) -> Result<Acknowledgement, Error> {
+        let mut event = event;
+        let request_id = self.request_id(&mut event);
+        let (acknowledgement, ack) = Acknowledgement::new(request_id, self.ack_timeout);
+
+        self.command(event, Some(ack), None)?;
+
+        Ok(acknowledgement)
+    }
+
+    /// Candles of one instrument. They're delivered to the returned stream instead of
+    /// the session, which keeps reporting connection events and everything else.
+    pub fn subscribe_candles(
+        &self,
+        figi: &Figi,
+        interval: Interval,
+    ) -> Result<SubscriptionStream<CandleEventPayload>, Error> {
+        let event = OutcomeEvent::CandleSubscribe {
+            figi: figi.clone(),
+            interval: interval,
+            request_id: None,
+        };
+
+        self.subscribe_stream(event, |e| match e {
+            IncomeEvent::Candle { payload, .. } => Some(payload),
+            _ => None,
+        })
+    }
+
+    /// Order books of one instrument, see [`subscribe_candles`](Self::subscribe_candles).
+    pub fn subscribe_orderbook(
+        &self,
+        figi: &Figi,
+        depth: i32,
+    ) -> Result<SubscriptionStream<OrderBookEventPayload>, Error> {
+        let event = OutcomeEvent::OrderbookSubscribe {
+            figi: figi.clone(),
+            depth: depth,
+            request_id: None,
+        };
+
+        self.subscribe_stream(event, |e| match e {
+            IncomeEvent::OrderBook { payload, .. } => Some(payload),
+            _ => None,
+        })
+    }
+
+    /// Instrument info of one instrument, see [`subscribe_candles`](Self::subscribe_candles).
+    pub fn subscribe_instrument_info(
+        &self,
+        figi: &Figi,
+    ) -> Result<SubscriptionStream<InstrumentInfoEventPayload>, Error> {
+        let event = OutcomeEvent::InstrumentInfoSubscribe {
+            figi: figi.clone(),
+            request_id: None,
+        };
+
+        self.subscribe_stream(event, |e| match e {
+            IncomeEvent::InstrumentInfo { payload, .. } => Some(payload),
+            _ => None,
+        })
+    }
+
+    /// Subscriptions the session holds and restores after reconnecting. Rejected
+    /// subscriptions are removed.
+    pub fn subscriptions(&self) -> Vec<Subscription> {
+        self.registry.lock().unwrap().subscriptions()
+    }
+
+    /// Moves the session events out, leaving the session stream ended.
+    pub(crate) fn take_events(&mut self) -> mpsc::Receiver<SessionEvent> {
+        std::mem::replace(&mut self.events, mpsc::channel(1).1)
+    }
+
+    fn subscribe_stream<T>(
+        &self,
+        mut event: OutcomeEvent,
+        extract: fn(IncomeEvent) -> Option<T>,
+    ) -> Result<SubscriptionStream<T>, Error> {
+        let request_id = self.request_id(&mut event);
+        let (route, events) = mpsc::channel(self.buffer);
+
+        self.command(event, None, Some(route))?;
+
+        Ok(SubscriptionStream::new(
+            request_id,
+            events,
+            self.commands.clone(),
+            extract,
+        ))
+    }
+
+    fn command(
+        &self,
+        mut event: OutcomeEvent,
+        ack: Option<Ack>,
+        route: Option<Route>,
+    ) -> Result<(), Error> {
+        self.request_id(&mut event);
+
+        self.commands
+            .send(Command::Send {
+                event: event,
+                ack: ack,
+                route: route,
+            })
+            .map_err(|_| Error::GeneralError {
+                description: "Streaming session is closed".to_string(),
+            })
+    }
+
+    fn request_id(&self, event: &mut OutcomeEvent) -> String {
+        assign_request_id(event, || {
+            self.next_request_id
+                .fetch_add(1, Ordering::Relaxed)
+                .to_string()
+        })
+    }
+}
+
+impl Stream for StreamingSession {
+    type Item = SessionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<SessionEvent>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+/// Runs until the session and all its subscription streams are dropped
+/// or the reconnect policy is exhausted.
+async fn run(
+    connector: Connector,
+    config: SessionConfig,
+    connection: (EventSink, EventStream),
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::Sender<SessionEvent>,
+    registry: Arc<Mutex<Registry>>,
+) {
+    let events = Events {
+        sender: events,
+        dropped: 0,
+    };
+    drive(connector, config, connection, commands, events, &registry).await;
+
+    // Ends the subscription streams and fails pending acknowledgements.
+    registry.lock().unwrap().clear();
+}
+
+async fn drive(
+    connector: Connector,
+    config: SessionConfig,
+    connection: (EventSink, EventStream),
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    mut events: Events,
+    registry: &Mutex<Registry>,
+) {
+    let (mut sink, mut stream) = connection;
+    #[cfg(feature = "metrics")]
+    let mut gauge = telemetry::SubscriptionGauge::default();
+
+    loop {
+        let mut ping =
+            time::interval_at(Instant::now() + config.ping_interval, config.ping_interval);
+        ping.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let stale = time::sleep(config.stale_timeout);
+        tokio::pin!(stale);
+
+        let error = loop {
+            #[cfg(feature = "metrics")]
+            gauge.update(registry.lock().unwrap().active());
+
+            tokio::select! {
+                _ = ping.tick(), if config.keepalive => {
+                    if let Err(e) = sink.send(OutcomeEvent::Ping(vec![])).await {
+                        break e;
+                    }
+                }
+                _ = &mut stale, if config.keepalive => {
+                    break Error::StaleConnectionError {
+                        description: format!(
+                            "No messages received for {} ms",
+                            config.stale_timeout.as_millis()
+                        ),
+                    };
+                }
+                command = commands.recv() => match command {
+                    Some(Command::Send { event, ack, route }) => {
+                        registry.lock().unwrap().track(&event, ack, route);
+                        if let Err(e) = sink.send(event).await {
+                            break e;
+                        }
+                    }
+                    Some(Command::Release(request_id)) => {
+                        let event = registry.lock().unwrap().release(&request_id);
+                        if let Some(event) = event {
+                            if let Err(e) = sink.send(event).await {
+                                break e;
+                            }
+                        }
+                    }
+                    None => {
+                        let _ = sink.close().await;
+                        return;
+                    }
+                },
+                message = stream.next() => {
+                    stale.as_mut().reset(Instant::now() + config.stale_timeout);
+
+                    let event = match message {
+                        Some(Ok(IncomeEvent::Ping(_))) | Some(Ok(IncomeEvent::Pong(_))) => continue,
+                        Some(Ok(IncomeEvent::Close)) | None => break connection_closed(),
+                        Some(Ok(event)) => {
+                            let routed = registry.lock().unwrap().confirm(&event);
+                            if let Some((request_id, route)) = routed {
+                                // A stream dropped but not released yet loses the event. One that
+                                // fell behind is ended instead of holding up the other subscriptions.
+                                if let Err(TrySendError::Full(_)) = route.try_send(event) {
+                                    let unsubscribe = registry.lock().unwrap().release(&request_id);
+                                    events.send(SessionEvent::Error(Error::SubscriptionError {
+                                        request_id: request_id,
+                                        description: format!(
+                                            "Subscription stream fell {} events behind and was closed",
+                                            config.buffer
+                                        ),
+                                    }));
+                                    if let Some(unsubscribe) = unsubscribe {
+                                        if let Err(e) = sink.send(unsubscribe).await {
+                                            break e;
+                                        }
+                                    }
+                                }
+                                continue;
+                            }
+                            SessionEvent::Event(event)
+                        }
+                        Some(Err(e)) if e.kind() == ErrorKind::Serialization => SessionEvent::Error(e),
+                        Some(Err(e)) => break e,
+                    };
+
+                    events.send(event);
+                }
+            }
+        };
+
+        #[cfg(feature = "tracing")]
+        tracing::warn!(error = %error, "Streaming connection lost");
+
+        registry.lock().unwrap().reset();
+        #[cfg(feature = "metrics")]
+        gauge.update(0);
+
+        events.send(SessionEvent::Disconnected(error));
+
+        match reconnect(
+            &connector,
+            &config.reconnect_policy,
+            &mut commands,
+            registry,
+        )
+        .await
+        {
+            Ok(connection) => {
+                sink = connection.0;
+                stream = connection.1;
+            }
+            Err(Some(error)) => {
+                events.send(SessionEvent::Disconnected(error));
+                return;
+            }
+            Err(None) => return,
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::info!(
+            subscriptions = registry.lock().unwrap().len(),
+            "Streaming connection restored"
+        );
+        #[cfg(feature = "metrics")]
+        telemetry::record_reconnect();
+
+        events.send(SessionEvent::Reconnected);
+    }
+}
+
+/// Delivers session events without waiting for the consumer, so that an unpolled
+/// session doesn't stop the socket from being read.
+struct Events {
+    sender: mpsc::Sender<SessionEvent>,
+    dropped: u64,
+}
+
+impl Events {
+    fn send(&mut self, event: SessionEvent) {
+        if self.dropped > 0 {
+            match self.sender.try_send(SessionEvent::Lagged(self.dropped)) {
+                Ok(()) => self.dropped = 0,
+                Err(TrySendError::Full(_)) => {
+                    self.dropped += 1;
+                    return;
+                }
+                // The session was dropped while typed streams keep the task running.
+                Err(TrySendError::Closed(_)) => return,
+            }
+        }
+
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(event) {
+            self.dropped += 1;
+        }
+    }
+}
+
+/// Connects again and replays `subscriptions`. Fails with the last error once the policy
+/// is exhausted, or with `None` when the session was dropped meanwhile.
+async fn reconnect(
+    connector: &Connector,
+    policy: &RetryPolicy,
+    commands: &mut mpsc::UnboundedReceiver<Command>,
+    registry: &Mutex<Registry>,
+) -> Result<(EventSink, EventStream), Option<Error>> {
+    let mut attempt = 1;
+
+    loop {
+        let delay = tokio::time::sleep(policy.delay(attempt, None));
+        tokio::pin!(delay);
+
+        // Subscriptions changed while disconnected are only recorded, the replay sends them.
+        loop {
+            tokio::select! {
+                _ = &mut delay => break,
+                command = commands.recv() => match command {
+                    Some(Command::Send { event, ack, route }) => {
+                        registry.lock().unwrap().track(&event, ack, route)
+                    }
+                    Some(Command::Release(request_id)) => {
+                        registry.lock().unwrap().release(&request_id);
+                    }
+                    None => return Err(None),
+                },
+            }
+        }
+
+        let error = match connector.connect().await {
+            Ok((mut sink, stream)) => match replay(&mut sink, registry).await {
+                Ok(()) => return Ok((sink, stream)),
+                Err(e) => e,
+            },
+            Err(e) => e,
+        };
+
+        if !policy.allows(true, attempt) {
+            return Err(Some(error));
+        }
+        attempt += 1;
+    }
+}
+
+async fn replay(sink: &mut EventSink, registry: &Mutex<Registry>) -> Result<(), Error> {
+    let subscriptions = registry.lock().unwrap().events();
+
+    for subscription in subscriptions {
+        sink.feed(subscription).await?;
+    }
+
+    sink.flush().await
+}
+
+fn connection_closed() -> Error {
+    Error::from(tokio_tungstenite::tungstenite::Error::ConnectionClosed)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::errors::Error;
+    use crate::session::{SessionConfig, SessionEvent};
+    use crate::subscription::SubscriptionState;
+    use crate::{Environment, RetryPolicy, TinkoffInvestClient};
+    use futures::{SinkExt, StreamExt};
+    use std::net::SocketAddr;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::accept_async;
+    use tokio_tungstenite::tungstenite::Message;
+
+    fn client(address: SocketAddr) -> TinkoffInvestClient {
+        TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: "http://127.0.0.1:1".to_string(),
+                streaming: format!("ws://{}", address),
+            })
+            .build()
+            .unwrap()
+    }
+
+    fn config(max_attempts: u32) -> SessionConfig {
+        SessionConfig::default().reconnect_policy(
+            RetryPolicy::default()
+                .max_attempts(max_attempts)
+                .base_delay(Duration::from_millis(1))
+                .jitter(false),
+        )
+    }
+
+    fn candle_subscription(figi: &str, subscribe: bool) -> OutcomeEvent {
+        let figi = Figi::new(figi).unwrap();
+        let interval = Interval::_1min;
+        let request_id = None;
+
+        if subscribe {
+            OutcomeEvent::CandleSubscribe {
+                figi,
+                interval,
+                request_id,
+            }
+        } else {
+            OutcomeEvent::CandleUnsubscribe {
+                figi,
+                interval,
+                request_id,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn resubscribes_after_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            for _ in 0..3 {
+                ws.next().await.unwrap().unwrap();
+            }
+            ws.close(None).await.unwrap();
+
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            let replayed = ws.next().await.unwrap().unwrap();
+            ws.send(Message::text(
+                "{\"event\": \"error\", \"time\": \"2021-11-01T10:00:00+03:00\", \
+                 \"payload\": {\"error\": \"Unknown figi\", \"request_id\": null}}",
+            ))
+            .await
+            .unwrap();
+
+            replayed.into_text().unwrap()
+        });
+
+        let mut session = client(address).streaming_session(config(5)).await.unwrap();
+        session
+            .send(candle_subscription("BBG004730N88", true))
+            .unwrap();
+        session
+            .send(candle_subscription("BBG004731032", true))
+            .unwrap();
+        session
+            .send(candle_subscription("BBG004730N88", false))
+            .unwrap();
+
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Disconnected(_))
+        ));
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Reconnected)
+        ));
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Event(IncomeEvent::Error { .. }))
+        ));
+
+        let replayed = server.await.unwrap();
+        assert!(replayed.contains("candle:subscribe"));
+        assert!(replayed.contains("BBG004731032"));
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            ws.close(None).await.unwrap();
+        });
+
+        let mut session = client(address).streaming_session(config(2)).await.unwrap();
+
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Disconnected(_))
+        ));
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Disconnected(_))
+        ));
+        assert!(session.next().await.is_none());
+        assert!(session
+            .send(candle_subscription("BBG004730N88", true))
+            .is_err());
+    }
+
+    fn keepalive_config() -> SessionConfig {
+        config(1)
+            .ping_interval(Duration::from_millis(10))
+            .stale_timeout(Duration::from_millis(100))
+    }
+
+    #[tokio::test]
+    async fn drops_stale_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let _ws = accept_async(socket).await.unwrap();
+            // Never reads, so pings are left unanswered.
+            tokio::time::sleep(Duration::from_secs(10)).await;
+        });
+
+        let mut session = client(address)
+            .streaming_session(keepalive_config())
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Disconnected(
+                Error::StaleConnectionError { .. }
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn keeps_answered_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            // Reading answers pings with pongs.
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let mut session = client(address)
+            .streaming_session(keepalive_config())
+            .await
+            .unwrap();
+
+        assert!(
+            tokio::time::timeout(Duration::from_millis(300), session.next())
+                .await
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn acknowledges_subscriptions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+
+            let rejected = ws.next().await.unwrap().unwrap().into_text().unwrap();
+            let rejected: serde_json::Value = serde_json::from_str(&rejected).unwrap();
+            ws.send(Message::text(format!(
+                "{{\"event\": \"error\", \"time\": \"2021-11-01T10:00:00+03:00\", \
+                 \"payload\": {{\"error\": \"Unknown figi\", \"request_id\": {}}}}}",
+                rejected["request_id"]
+            )))
+            .await
+            .unwrap();
+
+            ws.next().await.unwrap().unwrap();
+            ws.send(candle("BBG004731032")).await.unwrap();
+
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let mut session = client(address).streaming_session(config(1)).await.unwrap();
+
+        let rejected = session
+            .subscribe(candle_subscription("BBG004730N88", true))
+            .unwrap();
+        let rejected_id = rejected.request_id().to_string();
+        assert!(matches!(
+            rejected.await,
+            Err(Error::SubscriptionError { request_id, .. }) if request_id == rejected_id
+        ));
+        assert!(session.subscriptions().is_empty());
+
+        let accepted = session
+            .subscribe(candle_subscription("BBG004731032", true))
+            .unwrap();
+        assert_ne!(accepted.request_id(), rejected_id);
+        accepted.await.unwrap();
+
+        let subscriptions = session.subscriptions();
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions[0].state(), SubscriptionState::Active);
+
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Event(IncomeEvent::Error { .. }))
+        ));
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Event(IncomeEvent::Candle { .. }))
+        ));
+    }
+
+    #[tokio::test]
+    async fn acknowledges_after_silence() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let session = client(address)
+            .streaming_session(config(1).ack_timeout(Duration::from_millis(100)))
+            .await
+            .unwrap();
+
+        let acknowledgement = session
+            .subscribe(candle_subscription("BBG004730N88", true))
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(5), acknowledgement)
+            .await
+            .expect("acknowledgement should resolve after ack_timeout")
+            .unwrap();
+    }
+
+    fn candle(figi: &str) -> Message {
+        Message::text(format!(
+            "{{\"event\": \"candle\", \"time\": \"2021-11-01T10:00:00+03:00\", \
+             \"payload\": {{\"o\": 1.0, \"c\": 1.0, \"h\": 1.0, \"l\": 1.0, \"v\": 10, \
+             \"time\": \"2021-11-01T10:00:00+03:00\", \"interval\": \"1min\", \"figi\": \"{}\"}}}}",
+            figi
+        ))
+    }
+
+    #[tokio::test]
+    async fn routes_events_to_subscription_streams() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.next().await.unwrap().unwrap();
+
+            ws.send(candle("BBG004731032")).await.unwrap();
+            ws.send(candle("BBG004730N88")).await.unwrap();
+
+            ws.next().await.unwrap().unwrap().into_text().unwrap()
+        });
+
+        let mut session = client(address).streaming_session(config(1)).await.unwrap();
+        let mut candles = session
+            .subscribe_candles(&Figi::new("BBG004730N88").unwrap(), Interval::_1min)
+            .unwrap();
+        session
+            .send(candle_subscription("BBG004731032", true))
+            .unwrap();
+
+        assert_eq!(candles.next().await.unwrap().figi.as_str(), "BBG004730N88");
+        match session.next().await {
+            Some(SessionEvent::Event(IncomeEvent::Candle { payload, .. })) => {
+                assert_eq!(payload.figi.as_str(), "BBG004731032")
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        let request_id = candles.request_id().to_string();
+        drop(candles);
+
+        let unsubscription: serde_json::Value =
+            serde_json::from_str(&server.await.unwrap()).unwrap();
+        assert_eq!(unsubscription["event"], "candle:unsubscribe");
+        assert_eq!(unsubscription["figi"], "BBG004730N88");
+        assert_eq!(unsubscription["request_id"], request_id.as_str());
+        assert_eq!(session.subscriptions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn ends_streams_that_fall_behind() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.next().await.unwrap().unwrap();
+
+            for _ in 0..6 {
+                ws.send(candle("BBG004730N88")).await.unwrap();
+            }
+            for _ in 0..3 {
+                ws.send(candle("BBG004731032")).await.unwrap();
+            }
+
+            ws.next().await.unwrap().unwrap().into_text().unwrap()
+        });
+
+        let mut session = client(address)
+            .streaming_session(config(1).buffer(4))
+            .await
+            .unwrap();
+        let idle = session
+            .subscribe_candles(&Figi::new("BBG004730N88").unwrap(), Interval::_1min)
+            .unwrap();
+        let mut polled = session
+            .subscribe_candles(&Figi::new("BBG004731032").unwrap(), Interval::_1min)
+            .unwrap();
+
+        for _ in 0..3 {
+            let candle = tokio::time::timeout(Duration::from_secs(5), polled.next())
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!(candle.figi.as_str(), "BBG004731032");
+        }
+
+        let idle_id = idle.request_id().to_string();
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Error(Error::SubscriptionError { request_id, .. })) if request_id == idle_id
+        ));
+        assert_eq!(idle.collect::<Vec<_>>().await.len(), 4);
+
+        let unsubscription: serde_json::Value =
+            serde_json::from_str(&server.await.unwrap()).unwrap();
+        assert_eq!(unsubscription["event"], "candle:unsubscribe");
+        assert_eq!(unsubscription["figi"], "BBG004730N88");
+        assert_eq!(session.subscriptions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reports_lag_of_unpolled_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let (resume, resumed) = tokio::sync::oneshot::channel::<()>();
+
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = accept_async(socket).await.unwrap();
+            ws.next().await.unwrap().unwrap();
+            ws.next().await.unwrap().unwrap();
+
+            for _ in 0..5 {
+                ws.send(candle("BBG004730N88")).await.unwrap();
+            }
+            ws.send(candle("BBG004731032")).await.unwrap();
+
+            resumed.await.unwrap();
+            ws.send(candle("BBG004730N88")).await.unwrap();
+
+            while let Some(Ok(_)) = ws.next().await {}
+        });
+
+        let mut session = client(address)
+            .streaming_session(config(1).buffer(2))
+            .await
+            .unwrap();
+        session
+            .send(candle_subscription("BBG004730N88", true))
+            .unwrap();
+        let mut polled = session
+            .subscribe_candles(&Figi::new("BBG004731032").unwrap(), Interval::_1min)
+            .unwrap();
+
+        // Reaches the stream although the session isn't polled.
+        let candle = tokio::time::timeout(Duration::from_secs(5), polled.next())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(candle.figi.as_str(), "BBG004731032");
+
+        for _ in 0..2 {
+            assert!(matches!(
+                session.next().await,
+                Some(SessionEvent::Event(IncomeEvent::Candle { .. }))
+            ));
+        }
+
+        resume.send(()).unwrap();
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Lagged(3))
+        ));
+        assert!(matches!(
+            session.next().await,
+            Some(SessionEvent::Event(IncomeEvent::Candle { .. }))
+        ));
+    }
+}
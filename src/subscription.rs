@@ -0,0 +1,400 @@
+use crate::domain::*;
+use crate::errors::Error;
+use futures::stream::Stream;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+use tokio::time::{self, Sleep};
+
+pub(crate) type Ack = oneshot::Sender<Result<(), Error>>;
+pub(crate) type Route = mpsc::Sender<IncomeEvent>;
+
+/// Request to the task of a session.
+pub(crate) enum Command {
+    /// Send an event, optionally tracking its acknowledgement and delivering
+    /// the events of its channel to a dedicated stream.
+    Send {
+        event: OutcomeEvent,
+        ack: Option<Ack>,
+        route: Option<Route>,
+    },
+    /// Unsubscribe the subscription with this `request_id` if it's still held.
+    Release(String),
+}
+
+/// Whether the server has confirmed a subscription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubscriptionState {
+    /// Sent, but nothing has arrived on its channel yet. Subscriptions return
+    /// to this state while the session reconnects.
+    Pending,
+    /// Events have arrived on its channel.
+    Active,
+}
+
+/// Subscription held by a [`StreamingSession`](crate::StreamingSession).
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    request_id: String,
+    event: OutcomeEvent,
+    state: SubscriptionState,
+}
+
+impl Subscription {
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    /// The subscribe event sent to the server.
+    pub fn event(&self) -> &OutcomeEvent {
+        &self.event
+    }
+
+    pub fn state(&self) -> SubscriptionState {
+        self.state
+    }
+}
+
+/// Outcome of a subscription sent with [`StreamingSession::subscribe`](crate::StreamingSession::subscribe).
+///
+/// The streaming API only answers a subscription with an error, so it resolves to `Ok`
+/// on the first event of the subscribed channel and to a
+/// [`SubscriptionError`](Error::SubscriptionError) when the server rejects its `request_id`.
+/// When neither arrives within [`SessionConfig::ack_timeout`](crate::SessionConfig::ack_timeout),
+/// e.g. for an instrument that isn't traded at the moment, the subscription is taken
+/// as accepted and it resolves to `Ok` as well. An unsubscription resolves right away.
+pub struct Acknowledgement {
+    request_id: String,
+    receiver: oneshot::Receiver<Result<(), Error>>,
+    deadline: Pin<Box<Sleep>>,
+}
+
+impl Acknowledgement {
+    pub(crate) fn new(request_id: String, timeout: Duration) -> (Self, Ack) {
+        let (sender, receiver) = oneshot::channel();
+
+        let acknowledgement = Self {
+            request_id: request_id,
+            receiver: receiver,
+            deadline: Box::pin(time::sleep(timeout)),
+        };
+
+        (acknowledgement, sender)
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+}
+
+impl Future for Acknowledgement {
+    type Output = Result<(), Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Poll::Ready(result) = Pin::new(&mut self.receiver).poll(cx) {
+            return Poll::Ready(result.unwrap_or_else(|_| {
+                Err(Error::GeneralError {
+                    description: "Subscription was replaced or its streaming session closed"
+                        .to_string(),
+                })
+            }));
+        }
+
+        self.deadline.as_mut().poll(cx).map(Ok)
+    }
+}
+
+/// Events of a single subscription, returned by
+/// [`StreamingSession::subscribe_candles`](crate::StreamingSession::subscribe_candles)
+/// and its siblings.
+///
+/// The stream ends when the subscription is rejected, replaced by another subscription to
+/// the same channel or the session gives up reconnecting. It also ends, after reporting a
+/// [`SubscriptionError`](Error::SubscriptionError) through the session, when it falls more
+/// than [`SessionConfig::buffer`](crate::SessionConfig::buffer) events behind, so that a
+/// stream nobody polls can't hold up the others. Dropping it unsubscribes.
+pub struct SubscriptionStream<T> {
+    request_id: String,
+    events: mpsc::Receiver<IncomeEvent>,
+    commands: mpsc::UnboundedSender<Command>,
+    extract: fn(IncomeEvent) -> Option<T>,
+}
+
+impl<T> SubscriptionStream<T> {
+    pub(crate) fn new(
+        request_id: String,
+        events: mpsc::Receiver<IncomeEvent>,
+        commands: mpsc::UnboundedSender<Command>,
+        extract: fn(IncomeEvent) -> Option<T>,
+    ) -> Self {
+        Self {
+            request_id: request_id,
+            events: events,
+            commands: commands,
+            extract: extract,
+        }
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+}
+
+impl<T> Stream for SubscriptionStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        loop {
+            match self.events.poll_recv(cx) {
+                Poll::Ready(Some(event)) => {
+                    if let Some(item) = (self.extract)(event) {
+                        return Poll::Ready(Some(item));
+                    }
+                }
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<T> Drop for SubscriptionStream<T> {
+    fn drop(&mut self) {
+        let _ = self
+            .commands
+            .send(Command::Release(self.request_id.clone()));
+    }
+}
+
+struct Entry {
+    subscription: Subscription,
+    ack: Option<Ack>,
+    route: Option<Route>,
+}
+
+/// Subscriptions of a session, keyed by channel.
+#[derive(Default)]
+pub(crate) struct Registry {
+    entries: Vec<Entry>,
+}
+
+impl Registry {
+    /// Updates the subscriptions with an event about to be sent.
+    pub(crate) fn track(&mut self, event: &OutcomeEvent, ack: Option<Ack>, route: Option<Route>) {
+        let (target, subscribe) = match channel(event) {
+            Some(channel) => channel,
+            None => return resolve(ack, Ok(())),
+        };
+
+        self.entries
+            .retain(|e| !matches!(channel(&e.subscription.event), Some((c, _)) if c == target));
+
+        if subscribe {
+            self.entries.push(Entry {
+                subscription: Subscription {
+                    request_id: request_id(event).unwrap_or_default().to_string(),
+                    event: event.clone(),
+                    state: SubscriptionState::Pending,
+                },
+                ack: ack,
+                route: route,
+            });
+        } else {
+            resolve(ack, Ok(()));
+        }
+    }
+
+    /// Confirms subscriptions with incoming events and drops the rejected ones.
+    /// Returns the subscription the event belongs to and its stream, if it has one.
+    pub(crate) fn confirm(&mut self, event: &IncomeEvent) -> Option<(String, Route)> {
+        let target = match event {
+            IncomeEvent::Candle { payload, .. } => {
+                Channel::Candle(&payload.figi, &payload.interval)
+            }
+            IncomeEvent::OrderBook { payload, .. } => {
+                Channel::OrderBook(&payload.figi, payload.depth)
+            }
+            IncomeEvent::InstrumentInfo { payload, .. } => Channel::InstrumentInfo(&payload.figi),
+            IncomeEvent::Error { payload, .. } => {
+                if let Some(id) = &payload.request_id {
+                    self.reject(id, &payload.error);
+                }
+                return None;
+            }
+            _ => return None,
+        };
+
+        let entry = self
+            .entries
+            .iter_mut()
+            .find(|e| matches!(channel(&e.subscription.event), Some((c, _)) if c == target))?;
+
+        entry.subscription.state = SubscriptionState::Active;
+        resolve(entry.ack.take(), Ok(()));
+
+        let route = entry.route.clone()?;
+        Some((entry.subscription.request_id.clone(), route))
+    }
+
+    /// Drops the subscription with `request_id` and returns the event unsubscribing it.
+    /// Does nothing when the subscription was already replaced or removed.
+    pub(crate) fn release(&mut self, request_id: &str) -> Option<OutcomeEvent> {
+        let i = self
+            .entries
+            .iter()
+            .position(|e| e.subscription.request_id == request_id)?;
+
+        unsubscribe(self.entries.remove(i).subscription.event)
+    }
+
+    fn reject(&mut self, request_id: &str, description: &str) {
+        if let Some(i) = self
+            .entries
+            .iter()
+            .position(|e| e.subscription.request_id == request_id)
+        {
+            let entry = self.entries.remove(i);
+
+            resolve(
+                entry.ack,
+                Err(Error::SubscriptionError {
+                    request_id: request_id.to_string(),
+                    description: description.to_string(),
+                }),
+            );
+        }
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Marks all subscriptions unconfirmed after the connection was lost.
+    pub(crate) fn reset(&mut self) {
+        for entry in self.entries.iter_mut() {
+            entry.subscription.state = SubscriptionState::Pending;
+        }
+    }
+
+    /// Subscribe events to send again on a new connection.
+    pub(crate) fn events(&self) -> Vec<OutcomeEvent> {
+        self.entries
+            .iter()
+            .map(|e| e.subscription.event.clone())
+            .collect()
+    }
+
+    pub(crate) fn subscriptions(&self) -> Vec<Subscription> {
+        self.entries
+            .iter()
+            .map(|e| e.subscription.clone())
+            .collect()
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[cfg(feature = "metrics")]
+    pub(crate) fn active(&self) -> usize {
+        self.entries
+            .iter()
+            .filter(|e| e.subscription.state == SubscriptionState::Active)
+            .count()
+    }
+}
+
+fn resolve(ack: Option<Ack>, result: Result<(), Error>) {
+    if let Some(ack) = ack {
+        let _ = ack.send(result);
+    }
+}
+
+#[derive(PartialEq)]
+enum Channel<'a> {
+    Candle(&'a Figi, &'a Interval),
+    OrderBook(&'a Figi, i32),
+    InstrumentInfo(&'a Figi),
+}
+
+/// Channel of a subscription event and whether it subscribes or unsubscribes.
+fn channel(event: &OutcomeEvent) -> Option<(Channel<'_>, bool)> {
+    match event {
+        OutcomeEvent::CandleSubscribe { figi, interval, .. } => {
+            Some((Channel::Candle(figi, interval), true))
+        }
+        OutcomeEvent::CandleUnsubscribe { figi, interval, .. } => {
+            Some((Channel::Candle(figi, interval), false))
+        }
+        OutcomeEvent::OrderbookSubscribe { figi, depth, .. } => {
+            Some((Channel::OrderBook(figi, *depth), true))
+        }
+        OutcomeEvent::OrderbookUnsubscribe { figi, depth, .. } => {
+            Some((Channel::OrderBook(figi, *depth), false))
+        }
+        OutcomeEvent::InstrumentInfoSubscribe { figi, .. } => {
+            Some((Channel::InstrumentInfo(figi), true))
+        }
+        OutcomeEvent::InstrumentInfoUnsubscribe { figi, .. } => {
+            Some((Channel::InstrumentInfo(figi), false))
+        }
+        OutcomeEvent::Ping(_) | OutcomeEvent::Pong(_) => None,
+    }
+}
+
+fn unsubscribe(event: OutcomeEvent) -> Option<OutcomeEvent> {
+    match event {
+        OutcomeEvent::CandleSubscribe {
+            figi,
+            interval,
+            request_id,
+        } => Some(OutcomeEvent::CandleUnsubscribe {
+            figi,
+            interval,
+            request_id,
+        }),
+        OutcomeEvent::OrderbookSubscribe {
+            figi,
+            depth,
+            request_id,
+        } => Some(OutcomeEvent::OrderbookUnsubscribe {
+            figi,
+            depth,
+            request_id,
+        }),
+        OutcomeEvent::InstrumentInfoSubscribe { figi, request_id } => {
+            Some(OutcomeEvent::InstrumentInfoUnsubscribe { figi, request_id })
+        }
+        _ => None,
+    }
+}
+
+fn request_id(event: &OutcomeEvent) -> Option<&str> {
+    match event {
+        OutcomeEvent::CandleSubscribe { request_id, .. }
+        | OutcomeEvent::CandleUnsubscribe { request_id, .. }
+        | OutcomeEvent::OrderbookSubscribe { request_id, .. }
+        | OutcomeEvent::OrderbookUnsubscribe { request_id, .. }
+        | OutcomeEvent::InstrumentInfoSubscribe { request_id, .. }
+        | OutcomeEvent::InstrumentInfoUnsubscribe { request_id, .. } => request_id.as_deref(),
+        OutcomeEvent::Ping(_) | OutcomeEvent::Pong(_) => None,
+    }
+}
+
+/// Sets `request_id` on a subscription event that has none and returns the id the event ends up with.
+pub(crate) fn assign_request_id(event: &mut OutcomeEvent, next: impl FnOnce() -> String) -> String {
+    match event {
+        OutcomeEvent::CandleSubscribe { request_id, .. }
+        | OutcomeEvent::CandleUnsubscribe { request_id, .. }
+        | OutcomeEvent::OrderbookSubscribe { request_id, .. }
+        | OutcomeEvent::OrderbookUnsubscribe { request_id, .. }
+        | OutcomeEvent::InstrumentInfoSubscribe { request_id, .. }
+        | OutcomeEvent::InstrumentInfoUnsubscribe { request_id, .. } => {
+            request_id.get_or_insert_with(next).clone()
+        }
+        OutcomeEvent::Ping(_) | OutcomeEvent::Pong(_) => String::new(),
+    }
+}
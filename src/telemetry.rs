@@ -0,0 +1,277 @@
+//! Metrics recorded through the [`metrics`] facade when the `metrics` feature is enabled.
+//! Install any `metrics` compatible exporter, e.g. a Prometheus one, to collect them.
+
+use crate::domain::IncomeEvent;
+use crate::errors::Error;
+use metrics::{counter, describe_counter, describe_gauge, describe_histogram, gauge, histogram};
+use reqwest::Method;
+use std::time::Duration;
+
+pub const REQUESTS_TOTAL: &str = "tinkoff_invest_requests_total";
+pub const REQUEST_DURATION_SECONDS: &str = "tinkoff_invest_request_duration_seconds";
+pub const REQUEST_ERRORS_TOTAL: &str = "tinkoff_invest_request_errors_total";
+pub const STREAM_MESSAGES_TOTAL: &str = "tinkoff_invest_stream_messages_total";
+pub const STREAM_SUBSCRIPTIONS: &str = "tinkoff_invest_stream_subscriptions";
+pub const STREAM_RECONNECTS_TOTAL: &str = "tinkoff_invest_stream_reconnects_total";
+
+/// Registers descriptions of all metrics with the installed recorder.
+pub fn describe_metrics() {
+    describe_counter!(
+        REQUESTS_TOTAL,
+        "REST request attempts by method and endpoint"
+    );
+    describe_histogram!(
+        REQUEST_DURATION_SECONDS,
+        metrics::Unit::Seconds,
+        "REST request attempt latency by method and endpoint"
+    );
+    describe_counter!(
+        REQUEST_ERRORS_TOTAL,
+        "Failed REST request attempts by endpoint and error variant"
+    );
+    describe_counter!(
+        STREAM_MESSAGES_TOTAL,
+        "Streaming messages received by event type"
+    );
+    describe_gauge!(
+        STREAM_SUBSCRIPTIONS,
+        "Subscriptions of streaming sessions confirmed by the server"
+    );
+    describe_counter!(
+        STREAM_RECONNECTS_TOTAL,
+        "Streaming connections restored by a streaming session"
+    );
+}
+
+pub(crate) fn record_request<T>(
+    method: &Method,
+    endpoint: &str,
+    latency: Duration,
+    result: &Result<T, Error>,
+) {
+    let method = method.to_string();
+    let endpoint = endpoint.to_string();
+
+    counter!(REQUESTS_TOTAL, "method" => method.clone(), "endpoint" => endpoint.clone())
+        .increment(1);
+    histogram!(REQUEST_DURATION_SECONDS, "method" => method, "endpoint" => endpoint.clone())
+        .record(latency.as_secs_f64());
+
+    if let Err(e) = result {
+        counter!(REQUEST_ERRORS_TOTAL, "endpoint" => endpoint, "error" => error_name(e))
+            .increment(1);
+    }
+}
+
+pub(crate) fn record_stream_message(event: &IncomeEvent) {
+    counter!(STREAM_MESSAGES_TOTAL, "event" => event.name()).increment(1);
+}
+
+/// Share of the subscriptions gauge held by one streaming session. Dropping it
+/// takes the session's subscriptions off the gauge.
+#[derive(Default)]
+pub(crate) struct SubscriptionGauge {
+    reported: usize,
+}
+
+impl SubscriptionGauge {
+    /// Moves the gauge by the change in the session's active subscriptions.
+    pub(crate) fn update(&mut self, active: usize) {
+        let subscriptions = gauge!(STREAM_SUBSCRIPTIONS);
+
+        if active > self.reported {
+            subscriptions.increment((active - self.reported) as f64);
+        } else if active < self.reported {
+            subscriptions.decrement((self.reported - active) as f64);
+        }
+        self.reported = active;
+    }
+}
+
+impl Drop for SubscriptionGauge {
+    fn drop(&mut self) {
+        self.update(0);
+    }
+}
+
+pub(crate) fn record_reconnect() {
+    counter!(STREAM_RECONNECTS_TOTAL).increment(1);
+}
+
+fn error_name(error: &Error) -> &'static str {
+    match error {
+        Error::ServiceError { .. } => "ServiceError",
+        Error::HTTPClientError { .. } => "HTTPClientError",
+        Error::WSHTTPClientError { .. } => "WSHTTPClientError",
+        Error::WSClientError { .. } => "WSClientError",
+        Error::SerializationError { .. } => "SerializationError",
+        Error::UnexpectedResponseError { .. } => "UnexpectedResponseError",
+        Error::ConfigurationError { .. } => "ConfigurationError",
+        Error::ValidationError { .. } => "ValidationError",
+        Error::StaleConnectionError { .. } => "StaleConnectionError",
+        Error::SubscriptionError { .. } => "SubscriptionError",
+        Error::GeneralError { .. } => "GeneralError",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::errors::Error;
+    use crate::telemetry::{
+        record_request, SubscriptionGauge, REQUESTS_TOTAL, REQUEST_ERRORS_TOTAL,
+        STREAM_SUBSCRIPTIONS,
+    };
+    use metrics::{
+        Counter, CounterFn, Gauge, GaugeFn, Histogram, Key, KeyName, Metadata, Recorder,
+        SharedString, Unit,
+    };
+    use reqwest::Method;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    #[derive(Default)]
+    struct Total(AtomicU64);
+
+    impl CounterFn for Total {
+        fn increment(&self, value: u64) {
+            self.0.fetch_add(value, Ordering::SeqCst);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.0.store(value, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Default)]
+    struct Level(Mutex<f64>);
+
+    impl GaugeFn for Level {
+        fn increment(&self, value: f64) {
+            *self.0.lock().unwrap() += value;
+        }
+
+        fn decrement(&self, value: f64) {
+            *self.0.lock().unwrap() -= value;
+        }
+
+        fn set(&self, value: f64) {
+            *self.0.lock().unwrap() = value;
+        }
+    }
+
+    #[derive(Default)]
+    struct TestRecorder {
+        counters: Mutex<Vec<(String, Arc<Total>)>>,
+        gauges: Mutex<Vec<(String, Arc<Level>)>>,
+    }
+
+    impl TestRecorder {
+        fn total(&self, name: &str) -> u64 {
+            self.counters
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(key, _)| key == name)
+                .map(|(_, total)| total.0.load(Ordering::SeqCst))
+                .sum()
+        }
+
+        fn level(&self, name: &str) -> f64 {
+            self.gauges
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(key, _)| key == name)
+                .map(|(_, level)| *level.0.lock().unwrap())
+                .sum()
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            let labels: Vec<String> = key
+                .labels()
+                .map(|l| format!("{}={}", l.key(), l.value()))
+                .collect();
+            let name = format!("{}{{{}}}", key.name(), labels.join(","));
+            let total = Arc::new(Total::default());
+            self.counters.lock().unwrap().push((name, total.clone()));
+            Counter::from_arc(total)
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            let level = Arc::new(Level::default());
+            self.gauges
+                .lock()
+                .unwrap()
+                .push((key.name().to_string(), level.clone()));
+            Gauge::from_arc(level)
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+            Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn requests_and_errors() {
+        let recorder = TestRecorder::default();
+
+        metrics::with_local_recorder(&recorder, || {
+            let ok: Result<(), Error> = Ok(());
+            let failed: Result<(), Error> = Err(Error::GeneralError {
+                description: "failed".to_string(),
+            });
+
+            record_request(&Method::GET, "/portfolio", Duration::from_millis(5), &ok);
+            record_request(
+                &Method::GET,
+                "/portfolio",
+                Duration::from_millis(5),
+                &failed,
+            );
+        });
+
+        assert_eq!(
+            recorder.total(&format!(
+                "{}{{method=GET,endpoint=/portfolio}}",
+                REQUESTS_TOTAL
+            )),
+            2
+        );
+        assert_eq!(
+            recorder.total(&format!(
+                "{}{{endpoint=/portfolio,error=GeneralError}}",
+                REQUEST_ERRORS_TOTAL
+            )),
+            1
+        );
+    }
+
+    #[test]
+    fn subscriptions() {
+        let recorder = TestRecorder::default();
+
+        metrics::with_local_recorder(&recorder, || {
+            let mut first = SubscriptionGauge::default();
+            let mut second = SubscriptionGauge::default();
+
+            first.update(2);
+            second.update(3);
+            first.update(1);
+            assert_eq!(recorder.level(STREAM_SUBSCRIPTIONS), 4.0);
+
+            drop(second);
+            assert_eq!(recorder.level(STREAM_SUBSCRIPTIONS), 1.0);
+
+            first.update(0);
+            assert_eq!(recorder.level(STREAM_SUBSCRIPTIONS), 0.0);
+        });
+    }
+}
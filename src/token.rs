@@ -0,0 +1,216 @@
+use crate::errors::Error;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// Environment a token was issued for. The broker issues separate tokens for the
+/// sandbox and for live trading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenScope {
+    Production,
+    Sandbox,
+}
+
+/// API token which is never printed: `Debug` and `Display` show a placeholder.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SecretToken {
+    token: String,
+    scope: Option<TokenScope>,
+}
+
+impl SecretToken {
+    pub fn new(token: &str) -> Self {
+        Self::from(token.to_string())
+    }
+
+    /// Declares which environment the token belongs to, so that
+    /// [`SandboxClient`](crate::SandboxClient) and [`ProductionClient`](crate::ProductionClient)
+    /// refuse it for the other one. Unscoped tokens are accepted by both.
+    pub fn with_scope(mut self, scope: TokenScope) -> Self {
+        self.scope = Some(scope);
+        self
+    }
+
+    pub fn scope(&self) -> Option<TokenScope> {
+        self.scope
+    }
+
+    /// Reads the token from environment variable `name`.
+    pub fn from_env(name: &str) -> Result<Self, Error> {
+        let token = std::env::var(name).map_err(|e| Error::ConfigurationError {
+            description: format!("Can't read token from environment variable {}: {}", name, e),
+        })?;
+
+        Self::validated(token.trim(), name)
+    }
+
+    /// Reads the token from a file. On Unix the file must not be accessible by group or others.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let source = path.display().to_string();
+
+        let metadata = fs::metadata(path).map_err(|e| Error::ConfigurationError {
+            description: format!("Can't read token file {}: {}", source, e),
+        })?;
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = metadata.permissions().mode();
+            if mode & 0o077 != 0 {
+                return Err(Error::ConfigurationError {
+                    description: format!(
+                        "Token file {} is accessible by other users (mode {:o}), expected 600",
+                        source,
+                        mode & 0o777
+                    ),
+                });
+            }
+        }
+        #[cfg(not(unix))]
+        let _ = metadata;
+
+        let token = fs::read_to_string(path).map_err(|e| Error::ConfigurationError {
+            description: format!("Can't read token file {}: {}", source, e),
+        })?;
+
+        Self::validated(token.trim(), &source)
+    }
+
+    /// Raw token value. Keep it out of logs.
+    pub fn expose_secret(&self) -> &str {
+        &self.token
+    }
+
+    pub(crate) fn bearer(&self) -> String {
+        "Bearer ".to_owned() + &self.token
+    }
+
+    pub(crate) fn ensure_scope(&self, scope: TokenScope) -> Result<(), Error> {
+        match self.scope {
+            Some(token_scope) if token_scope != scope => Err(Error::ConfigurationError {
+                description: format!(
+                    "{:?} token can't be used with a {:?} client",
+                    token_scope, scope
+                ),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    fn validated(token: &str, source: &str) -> Result<Self, Error> {
+        if token.is_empty() {
+            return Err(Error::ConfigurationError {
+                description: format!("Token from {} is empty", source),
+            });
+        }
+
+        Ok(Self::new(token))
+    }
+}
+
+impl From<&str> for SecretToken {
+    fn from(token: &str) -> Self {
+        Self::new(token)
+    }
+}
+
+impl From<String> for SecretToken {
+    fn from(token: String) -> Self {
+        Self {
+            token: token,
+            scope: None,
+        }
+    }
+}
+
+impl fmt::Debug for SecretToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretToken(***)")
+    }
+}
+
+impl fmt::Display for SecretToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "***")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::token::SecretToken;
+    use crate::user::User;
+    use crate::{Environment, TinkoffInvestClient};
+    use mockito::Matcher;
+    use std::fs;
+
+    #[test]
+    fn redacted() {
+        let token = SecretToken::new("t.secret");
+
+        assert_eq!(format!("{:?}", token), "SecretToken(***)");
+        assert_eq!(format!("{}", token), "***");
+        assert_eq!(token.expose_secret(), "t.secret");
+    }
+
+    #[test]
+    fn from_env() {
+        std::env::set_var("TINKOFF_INVEST_CLIENT_TEST_TOKEN", "t.from_env\n");
+
+        let token = SecretToken::from_env("TINKOFF_INVEST_CLIENT_TEST_TOKEN").unwrap();
+        assert_eq!(token.expose_secret(), "t.from_env");
+
+        assert!(SecretToken::from_env("TINKOFF_INVEST_CLIENT_MISSING_TOKEN").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = std::env::temp_dir().join(format!("tinkoff-token-{}", std::process::id()));
+        fs::write(&path, "t.from_file\n").unwrap();
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        assert!(SecretToken::from_file(&path).is_err());
+
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+        let token = SecretToken::from_file(&path).unwrap();
+        assert_eq!(token.expose_secret(), "t.from_file");
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rotation() {
+        let body =
+            "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"accounts\": []}}";
+        let old_token = mockito::mock("GET", "/user/accounts")
+            .match_header("Authorization", "Bearer token123")
+            .match_query(Matcher::Missing)
+            .with_body(body)
+            .create();
+        let new_token = mockito::mock("GET", "/user/accounts")
+            .match_header("Authorization", "Bearer token456")
+            .match_query(Matcher::Missing)
+            .with_body(body)
+            .create();
+
+        let tinkoff = TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: mockito::server_url(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .build()
+            .unwrap();
+
+        tinkoff.accounts().await.unwrap();
+        tinkoff.set_token(SecretToken::new("token456")).unwrap();
+        tinkoff.accounts().await.unwrap();
+
+        old_token.assert();
+        new_token.assert();
+    }
+}
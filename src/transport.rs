@@ -0,0 +1,252 @@
+use crate::errors::Error;
+use async_trait::async_trait;
+use reqwest::header::AUTHORIZATION;
+use reqwest::{Client, Method};
+use std::fmt;
+
+/// REST request as handed to an [`HttpTransport`].
+/// Its `Debug` output hides the `Authorization` header.
+#[derive(Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub query: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    /// Value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+impl fmt::Debug for HttpRequest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headers: Vec<(&str, &str)> = self
+            .headers
+            .iter()
+            .map(|(n, v)| {
+                if n.eq_ignore_ascii_case(AUTHORIZATION.as_str()) {
+                    (n.as_str(), "***")
+                } else {
+                    (n.as_str(), v.as_str())
+                }
+            })
+            .collect();
+
+        f.debug_struct("HttpRequest")
+            .field("method", &self.method)
+            .field("url", &self.url)
+            .field("headers", &headers)
+            .field("query", &self.query)
+            .field("body", &self.body)
+            .finish()
+    }
+}
+
+/// Raw reply of an [`HttpTransport`], decoded into a [`Response`](crate::domain::Response) by the client.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Value of the first header named `name`, compared case-insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, v)| v.as_str())
+}
+
+/// HTTP stack used by [`TinkoffInvestClient`](crate::TinkoffInvestClient) for REST calls.
+///
+/// Only failures to exchange a request and a response are errors here;
+/// non-200 replies are returned as they are and decoded by the client.
+#[async_trait]
+pub trait HttpTransport: Send + Sync {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error>;
+}
+
+/// Default transport backed by [`reqwest::Client`].
+pub struct ReqwestTransport {
+    http_client: Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(http_client: Client) -> Self {
+        Self {
+            http_client: http_client,
+        }
+    }
+}
+
+#[async_trait]
+impl HttpTransport for ReqwestTransport {
+    async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+        let mut builder = self
+            .http_client
+            .request(request.method, &request.url)
+            .query(&request.query);
+
+        for (name, value) in &request.headers {
+            builder = builder.header(name.as_str(), value.as_str());
+        }
+
+        if let Some(body) = request.body {
+            builder = builder.body(body);
+        }
+
+        let result = builder.send().await?;
+
+        let status = result.status().as_u16();
+        let headers = result
+            .headers()
+            .iter()
+            .filter_map(|(n, v)| v.to_str().ok().map(|v| (n.to_string(), v.to_string())))
+            .collect();
+        let body = result.text().await?;
+
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use crate::domain::*;
+    use crate::orders::Orders;
+    use crate::transport::{HttpRequest, HttpResponse, HttpTransport};
+    use crate::user::User;
+    use crate::{Environment, Error, TinkoffInvestClient};
+    use async_trait::async_trait;
+    use reqwest::Method;
+    use std::sync::{Arc, Mutex};
+
+    struct FakeTransport {
+        response: HttpResponse,
+        requests: Mutex<Vec<HttpRequest>>,
+    }
+
+    impl FakeTransport {
+        fn new(status: u16, body: &str) -> Arc<Self> {
+            Arc::new(Self {
+                response: HttpResponse {
+                    status,
+                    headers: vec![],
+                    body: body.to_string(),
+                },
+                requests: Mutex::new(vec![]),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl HttpTransport for FakeTransport {
+        async fn send(&self, request: HttpRequest) -> Result<HttpResponse, Error> {
+            self.requests.lock().unwrap().push(request);
+            Ok(self.response.clone())
+        }
+    }
+
+    fn client(transport: Arc<FakeTransport>) -> TinkoffInvestClient {
+        TinkoffInvestClient::builder("token123")
+            .environment(Environment::Custom {
+                rest: "http://fake".to_string(),
+                streaming: "ws://127.0.0.1:1".to_string(),
+            })
+            .transport(transport)
+            .build()
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn get_request() {
+        let transport = FakeTransport::new(
+            200,
+            "{\"trackingId\": \"tracking_id_0\",\"status\": \"Ok\",\"payload\": {\"accounts\": []}}",
+        );
+
+        client(transport.clone()).accounts().await.unwrap();
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].method, Method::GET);
+        assert_eq!(requests[0].url, "http://fake/user/accounts");
+        assert_eq!(requests[0].header("authorization"), Some("Bearer token123"));
+        assert_eq!(requests[0].header("content-type"), Some("application/json"));
+        assert!(requests[0].query.is_empty());
+        assert_eq!(requests[0].body, None);
+        assert!(!format!("{:?}", requests[0]).contains("token123"));
+    }
+
+    #[tokio::test]
+    async fn post_request() {
+        let transport = FakeTransport::new(
+            200,
+            "{
+                \"trackingId\": \"tracking_id_0\",
+                \"status\": \"Ok\",
+                \"payload\": {
+                    \"orderId\": \"order_0\",
+                    \"operation\": \"Buy\",
+                    \"status\": \"Fill\",
+                    \"requestedLots\": 1,
+                    \"executedLots\": 1
+                }
+            }",
+        );
+
+        client(transport.clone())
+            .make_market_order(
+                &Figi::new("BBG000B9XRY4").unwrap(),
+                Some(&BrokerAccountId::new("account_123").unwrap()),
+                Operation::Buy,
+                1,
+            )
+            .await
+            .unwrap();
+
+        let requests = transport.requests.lock().unwrap();
+        let mut query = requests[0].query.clone();
+        query.sort();
+        assert_eq!(requests[0].method, Method::POST);
+        assert_eq!(requests[0].url, "http://fake/orders/market-order");
+        assert_eq!(
+            query,
+            vec![
+                ("brokerAccountId".to_string(), "account_123".to_string()),
+                ("figi".to_string(), "BBG000B9XRY4".to_string()),
+            ]
+        );
+        assert_eq!(
+            requests[0].body.as_deref(),
+            Some("{\"operation\":\"Buy\",\"lots\":1}")
+        );
+    }
+
+    #[tokio::test]
+    async fn error_response() {
+        let transport = FakeTransport::new(
+            401,
+            "{\"trackingId\": \"tracking_id_0\",\"status\": \"Error\",\"payload\": {\"message\": \"Unauthorized\"}}",
+        );
+
+        let error = client(transport).accounts().await.unwrap_err();
+
+        assert_eq!(error.kind(), crate::ErrorKind::Auth);
+    }
+}
@@ -7,6 +7,20 @@ use std::collections::HashMap;
 #[async_trait]
 pub trait User {
     async fn accounts(&self) -> Result<Response<AccountsPayload>, Error>;
+
+    /// First account of the given type, if the user has one.
+    async fn account_by_type(
+        &self,
+        broker_account_type: BrokerAccountType,
+    ) -> Result<Option<UserAccount>, Error> {
+        let accounts = self.accounts().await?;
+
+        Ok(accounts
+            .payload
+            .accounts
+            .into_iter()
+            .find(|a| a.broker_account_type == broker_account_type))
+    }
 }
 
 #[async_trait]
@@ -23,7 +37,6 @@ mod tests {
 
     use crate::user::User;
     use crate::TinkoffInvestClient;
-    use mockito;
     use mockito::Matcher;
 
     #[tokio::test]
@@ -52,7 +65,7 @@ mod tests {
 
         let endpoint = &mockito::server_url();
         let token = "token123";
-        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), &endpoint, &token);
+        let tinkoff = TinkoffInvestClient::new(reqwest::Client::new(), endpoint, token);
 
         tinkoff.accounts().await.unwrap();
 